@@ -0,0 +1,405 @@
+from fastapi import APIRouter, Depends, HTTPException, Request
+from fastapi.responses import StreamingResponse
+from sqlalchemy.ext.asyncio import AsyncSession
+from typing import Dict, Any, List, Optional
+from pydantic import BaseModel
+import asyncio
+import json
+
+from ..db.database import get_db_session
+from ..crud import games as crud_games
+from ..crud import analysis_jobs as crud_jobs
+from ..crud import analyses as crud_analyses
+from ..crud import analysis_presets as crud_presets
+from ..crud import engines as crud_engines
+from ..crud import settings as crud_settings
+from ..services.analysis_queue import enqueue_analysis, worker_pool
+from ..services.analysis_staleness import mark_stale_analyses
+from ..services.stockfish_service import get_stockfish_settings
+from ..services.system_resources import validate_settings
+from ..services.analysis_scope import validate_scope_options
+from ..services.analysis_events import event_broker
+from ..services.position_cache import purge_cache
+
+
+router = APIRouter()
+
+
+# Seconds between keep-alive comments on idle event streams
+EVENT_STREAM_HEARTBEAT = 15
+
+
+class EnqueueRequest(BaseModel):
+    game_ids: Optional[List[int]] = None
+    date_from: Optional[str] = None
+    date_to: Optional[str] = None
+    status: Optional[str] = None
+    opening: Optional[str] = None
+    preset: Optional[str] = None  # Analysis preset instead of each game's time-class default
+    scope: Optional[str] = None  # all, mine, critical or adaptive (ply ranges are per game)
+    engine: Optional[str] = None  # Engine profile instead of the default engine
+
+
+class PresetRequest(BaseModel):
+    name: Optional[str] = None  # Required when creating
+    depth: Optional[int] = None
+    time_ms: Optional[int] = None
+    multipv: Optional[int] = None
+
+
+def _validate_preset(request: PresetRequest):
+    # Presets follow the same limits as the analysis settings they replace
+    is_valid, errors = validate_settings({
+        key: value
+        for key, value in (
+            ("analysis_depth", request.depth),
+            ("analysis_time_ms", request.time_ms),
+            ("analysis_multipv", request.multipv)
+        )
+        if value is not None
+    })
+    if not is_valid:
+        raise HTTPException(status_code=400, detail={"message": "Invalid preset", "errors": errors})
+
+
+@router.get("/api/analysis/queue")
+async def get_analysis_queue(db: AsyncSession = Depends(get_db_session)) -> Dict[str, Any]:
+    """
+    Get the state of the background analysis queue.
+
+    Returns:
+        Job counts by status, worker count and the list of active jobs
+    """
+    stats = await crud_jobs.get_queue_stats(db)
+    active_jobs = await crud_jobs.get_active_jobs(db)
+
+    return {
+        "stats": stats,
+        "workers": worker_pool.worker_count,
+        "active_jobs": [
+            {
+                "job_id": job.id,
+                "game_id": job.game_id,
+                "status": job.status,
+                "priority": job.priority,
+                "attempts": job.attempts,
+                "created_at": job.created_at.isoformat() if job.created_at else None,
+                "started_at": job.started_at.isoformat() if job.started_at else None
+            }
+            for job in active_jobs
+        ]
+    }
+
+
+@router.post("/api/analysis/queue")
+async def enqueue_filtered_games(
+    request: EnqueueRequest,
+    db: AsyncSession = Depends(get_db_session)
+) -> Dict[str, Any]:
+    """
+    Queue analysis for an explicit list of games or for all games matching filters.
+    Games that are already analyzed or already queued are skipped, and with
+    the "mine" scope so are games the configured user didn't play.
+
+    Args:
+        request: Either game_ids, or date_from/date_to (YYYY-MM-DD), status and opening
+            filters, plus an optional analysis preset, scope and engine profile
+
+    Returns:
+        Dictionary with enqueue stats and the number of games left out as not played
+    """
+    if request.preset and not await crud_presets.get_preset_by_name(db, request.preset):
+        raise HTTPException(status_code=400, detail=f"Unknown analysis preset '{request.preset}'")
+    if request.engine and not await crud_engines.get_engine_by_name(db, request.engine):
+        raise HTTPException(status_code=400, detail=f"Unknown engine '{request.engine}'")
+
+    if request.scope == "range":
+        raise HTTPException(status_code=400, detail="Ply ranges can only be analysed per game")
+    settings = await crud_settings.get_all_settings(db)
+    errors = validate_scope_options(request.scope, None, None, settings.get("chess_com_username"))
+    if errors:
+        raise HTTPException(status_code=400, detail={"message": "Invalid analysis options", "errors": errors})
+
+    if request.game_ids:
+        game_ids = request.game_ids
+    else:
+        # Convert date format from YYYY-MM-DD to YYYY.MM.DD for database comparison
+        game_ids = await crud_games.get_game_ids(
+            db,
+            date_from=request.date_from.replace('-', '.') if request.date_from else None,
+            date_to=request.date_to.replace('-', '.') if request.date_to else None,
+            status=request.status,
+            opening=request.opening
+        )
+
+    # The "mine" scope can't be applied to games the user didn't play
+    not_played = 0
+    if request.scope == "mine":
+        played = await crud_games.get_games_played_by(db, game_ids, settings["chess_com_username"])
+        not_played = len(game_ids) - len(played)
+        game_ids = played
+
+    analysed = await crud_analyses.get_analysed_game_ids(db, game_ids)
+    game_ids = [game_id for game_id in game_ids if game_id not in analysed]
+    options = {
+        option: value
+        for option, value in (("preset", request.preset), ("scope", request.scope), ("engine", request.engine))
+        if value
+    }
+    result = await enqueue_analysis(game_ids, options=options or None)
+
+    message = f"Queued {result['enqueued']} games for analysis"
+    if not_played:
+        message += f" ({not_played} games by other players left out)"
+
+    return {
+        "success": True,
+        "message": message,
+        "not_played": not_played,
+        **result
+    }
+
+
+@router.post("/api/analysis/queue/all")
+async def enqueue_all_queued_games(db: AsyncSession = Depends(get_db_session)) -> Dict[str, Any]:
+    """
+    Queue analysis for every game whose analysis status is still 'queued'.
+
+    Returns:
+        Dictionary with enqueue stats
+    """
+    game_ids = await crud_games.get_game_ids(db, status='queued')
+    analysed = await crud_analyses.get_analysed_game_ids(db, game_ids)
+    game_ids = [game_id for game_id in game_ids if game_id not in analysed]
+    result = await enqueue_analysis(game_ids)
+
+    return {
+        "success": True,
+        "message": f"Queued {result['enqueued']} games for analysis",
+        **result
+    }
+
+
+@router.post("/api/analysis/queue/stale")
+async def enqueue_stale_games(db: AsyncSession = Depends(get_db_session)) -> Dict[str, Any]:
+    """
+    Queue a re-analysis with the current settings for every game whose analysis is stale.
+
+    The new analyses are stored as additional versions. Games keep the
+    engine profile of their latest analysis while that profile exists, as
+    the staleness check compares them with it.
+
+    Returns:
+        Dictionary with enqueue stats
+    """
+    game_ids = await crud_games.get_game_ids(db, status='stale')
+    latest = await crud_analyses.get_latest_analyses(db, game_ids)
+    engines = {engine.name for engine in await crud_engines.get_engines(db)}
+
+    # Empty overrides mark the jobs as re-analysis with the current settings
+    groups: Dict[str, List[int]] = {}
+    for game_id in game_ids:
+        profile = latest[game_id].engine_profile if game_id in latest else None
+        groups.setdefault(profile if profile in engines else "", []).append(game_id)
+
+    result = {"enqueued": 0, "skipped": 0, "total": 0}
+    for engine, group_ids in groups.items():
+        group_result = await enqueue_analysis(group_ids, options={"engine": engine} if engine else {})
+        for key in result:
+            result[key] += group_result[key]
+
+    return {
+        "success": True,
+        "message": f"Queued {result['enqueued']} stale games for re-analysis",
+        **result
+    }
+
+
+@router.post("/api/analysis/queue/cancel")
+async def cancel_analysis_queue() -> Dict[str, Any]:
+    """
+    Cancel every queued job and stop all running analyses.
+
+    Running analyses keep the moves analysed so far for a later resume.
+
+    Returns:
+        Dictionary with the number of cancelled queued jobs and stopped analyses
+    """
+    result = await worker_pool.cancel()
+
+    return {
+        "success": True,
+        "message": f"Cancelled {result['cancelled']} queued jobs, stopping {result['stopping']} running analyses",
+        **result
+    }
+
+
+@router.get("/api/analysis/presets")
+async def list_presets(db: AsyncSession = Depends(get_db_session)) -> Dict[str, Any]:
+    """
+    List the analysis presets, fastest first.
+
+    Returns:
+        Dictionary with the name and search settings of every preset
+    """
+    presets = await crud_presets.get_presets(db)
+
+    return {
+        "presets": [crud_presets.preset_to_dict(preset) for preset in presets]
+    }
+
+
+@router.post("/api/analysis/presets")
+async def create_preset(
+    request: PresetRequest,
+    db: AsyncSession = Depends(get_db_session)
+) -> Dict[str, Any]:
+    """
+    Create an analysis preset.
+
+    Args:
+        request: Name, depth, time_ms and multipv of the preset
+
+    Returns:
+        Dictionary with the created preset
+    """
+    if not request.name or request.depth is None or request.time_ms is None or request.multipv is None:
+        raise HTTPException(status_code=400, detail="Name, depth, time_ms and multipv are required")
+    _validate_preset(request)
+
+    if await crud_presets.get_preset_by_name(db, request.name):
+        raise HTTPException(status_code=409, detail=f"Preset '{request.name}' already exists")
+
+    preset = await crud_presets.create_preset(db, request.name, request.depth, request.time_ms, request.multipv)
+
+    return {
+        "success": True,
+        "preset": crud_presets.preset_to_dict(preset)
+    }
+
+
+@router.put("/api/analysis/presets/{name}")
+async def update_preset(
+    name: str,
+    request: PresetRequest,
+    db: AsyncSession = Depends(get_db_session)
+) -> Dict[str, Any]:
+    """
+    Change the search settings of an analysis preset.
+
+    Analyses of games whose time class uses the preset are checked for staleness again.
+
+    Args:
+        name: Preset name
+        request: New depth, time_ms and/or multipv
+
+    Returns:
+        Dictionary with the updated preset and the number of games marked stale
+    """
+    preset = await crud_presets.get_preset_by_name(db, name)
+
+    if not preset:
+        raise HTTPException(status_code=404, detail="Preset not found")
+    _validate_preset(request)
+
+    preset = await crud_presets.update_preset(db, preset, request.model_dump(exclude={"name"}))
+    staleness = await mark_stale_analyses(db)
+
+    return {
+        "success": True,
+        "preset": crud_presets.preset_to_dict(preset),
+        "stale_games": staleness["stale"],
+        "restored_games": staleness["restored"]
+    }
+
+
+@router.delete("/api/analysis/presets/{name}")
+async def delete_preset(name: str, db: AsyncSession = Depends(get_db_session)) -> Dict[str, Any]:
+    """
+    Delete an analysis preset that no time class uses as its default.
+
+    Args:
+        name: Preset name
+
+    Returns:
+        Success message
+    """
+    preset = await crud_presets.get_preset_by_name(db, name)
+
+    if not preset:
+        raise HTTPException(status_code=404, detail="Preset not found")
+
+    settings = await get_stockfish_settings(db)
+    in_use = [time_class for time_class, preset_name in settings["time_class_presets"].items() if preset_name == name]
+    if in_use:
+        raise HTTPException(
+            status_code=409,
+            detail=f"Preset '{name}' is the default for {', '.join(in_use)} games"
+        )
+
+    await crud_presets.delete_preset(db, preset)
+
+    return {
+        "success": True,
+        "message": f"Deleted preset '{name}'"
+    }
+
+
+@router.get("/api/analysis/events")
+async def stream_analysis_events(request: Request, game_id: Optional[int] = None):
+    """
+    Stream analysis progress as Server-Sent Events.
+
+    On connect, the latest progress of every running analysis is sent first.
+    Event types: queued, started, progress, completed, failed, cancelled.
+
+    Args:
+        game_id: Only stream events for this game (all games if omitted)
+
+    Returns:
+        text/event-stream response
+    """
+    queue = event_broker.subscribe(game_id)
+
+    async def event_stream():
+        try:
+            for event in event_broker.snapshot(game_id):
+                yield f"data: {json.dumps(event)}\n\n"
+
+            while not await request.is_disconnected():
+                try:
+                    event = await asyncio.wait_for(queue.get(), timeout=EVENT_STREAM_HEARTBEAT)
+                    yield f"data: {json.dumps(event)}\n\n"
+                except asyncio.TimeoutError:
+                    yield ": keep-alive\n\n"
+        finally:
+            event_broker.unsubscribe(queue)
+
+    return StreamingResponse(
+        event_stream(),
+        media_type="text/event-stream",
+        headers={
+            "Cache-Control": "no-cache",
+            "X-Accel-Buffering": "no"
+        }
+    )
+
+
+@router.delete("/api/analysis/position-cache")
+async def purge_position_cache(db: AsyncSession = Depends(get_db_session)) -> Dict[str, Any]:
+    """
+    Delete all cached position evaluations.
+
+    Existing game analyses are kept; positions are searched again
+    the next time they occur.
+
+    Returns:
+        Dictionary with the number of deleted entries
+    """
+    deleted = await purge_cache(db)
+
+    return {
+        "success": True,
+        "message": f"Deleted {deleted} cached positions",
+        "deleted": deleted
+    }
@@ -7,6 +7,7 @@ import os
 from pathlib import Path
 
 from ..db.database import get_db_session, engine, Base
+from ..db.migrations import run_migrations
 
 router = APIRouter()
 
@@ -38,6 +39,7 @@ async def initialize_database():
         # Create tables
         async with engine.begin() as conn:
             await conn.run_sync(Base.metadata.create_all)
+            await run_migrations(conn)
 
             # Initialize default settings
             await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('chess_com_username', NULL)"))
@@ -46,9 +48,47 @@ async def initialize_database():
             await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('stockfish_hash_mb', '128')"))
             await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('analysis_depth', '15')"))
             await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('analysis_time_ms', '1000')"))
+            await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('analysis_multipv', '3')"))
+            await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('analysis_position_retries', '2')"))
+            await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('analysis_search_timeout_s', '300')"))
+            await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('position_cache_enabled', 'true')"))
+            await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('book_enabled', 'true')"))
+            await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('book_max_ply', '20')"))
+            await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('book_polyglot_path', '')"))
+            await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('book_search_depth', '0')"))
+            await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('syzygy_path', '')"))
+            await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('syzygy_probe_limit', '7')"))
+            await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('analysis_limit_mode', 'depth_time')"))
+            await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('analysis_nodes', '1000000')"))
+            await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('analysis_mate_moves', '5')"))
+            await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('deterministic_mode', 'false')"))
+            await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('default_engine', '')"))
+            await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('critical_pass_depth', '10')"))
+            await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('critical_swing_cp', '100')"))
+            await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('adaptive_analysis_enabled', 'false')"))
+            await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('adaptive_time_budget_s', '120')"))
+            await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('analysis_workers', '1')"))
+            await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('engine_pool_size', '1')"))
+            await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('classification_best_cp', '10')"))
+            await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('classification_inaccuracy_cp', '50')"))
+            await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('classification_mistake_cp', '100')"))
+            await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('classification_blunder_cp', '300')"))
             await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('auto_sync_enabled', 'false')"))
             await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('theme', 'default')"))
 
+            # Presets per time class; empty uses the analysis settings above
+            await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('preset_bullet', 'quick')"))
+            await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('preset_blitz', '')"))
+            await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('preset_rapid', '')"))
+            await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('preset_classical', 'deep')"))
+
+            # Default analysis presets, only on first start so deleted presets stay deleted
+            result = await conn.execute(text("SELECT COUNT(*) FROM analysis_presets"))
+            if result.scalar() == 0:
+                await conn.execute(text("INSERT INTO analysis_presets (name, depth, time_ms, multipv) VALUES ('quick', 10, 200, 1)"))
+                await conn.execute(text("INSERT INTO analysis_presets (name, depth, time_ms, multipv) VALUES ('standard', 15, 1000, 3)"))
+                await conn.execute(text("INSERT INTO analysis_presets (name, depth, time_ms, multipv) VALUES ('deep', 22, 3000, 3)"))
+
         # Set proper permissions on database file
         if os.path.exists(DATABASE_PATH):
             os.chmod(DATABASE_PATH, 0o666)
@@ -75,7 +115,10 @@ async def clear_database(db: AsyncSession = Depends(get_db_session)):
         result = await db.execute(text("SELECT COUNT(*) FROM games"))
         count = result.scalar()
 
-        # Delete all games
+        # Delete all games with their analyses and analysis jobs
+        await db.execute(text("DELETE FROM analysed_positions"))
+        await db.execute(text("DELETE FROM analyses"))
+        await db.execute(text("DELETE FROM analysis_jobs"))
         await db.execute(text("DELETE FROM games"))
 
         # Commit the transaction
@@ -0,0 +1,153 @@
+from fastapi import APIRouter, Depends, HTTPException
+from sqlalchemy.ext.asyncio import AsyncSession
+from typing import Dict, Any, Optional, Union
+from pydantic import BaseModel
+
+from ..db.database import get_db_session
+from ..crud import engines as crud_engines
+from ..crud import settings as crud_settings
+from ..services.engine_profiles import identify_engine
+from ..services.analysis_staleness import mark_stale_analyses
+
+
+router = APIRouter()
+
+
+class EngineRequest(BaseModel):
+    name: Optional[str] = None  # Required when registering
+    label: Optional[str] = None
+    path: Optional[str] = None  # Required when registering
+    uci_options: Optional[Dict[str, Union[bool, int, float, str]]] = None
+
+
+async def _identify(path: str, uci_options: Optional[Dict[str, Any]]) -> Dict[str, Optional[str]]:
+    # The engine must start and know every option of the profile
+    try:
+        return await identify_engine(path, uci_options)
+    except ValueError as e:
+        raise HTTPException(status_code=400, detail=str(e))
+
+
+@router.get("/api/engines")
+async def list_engines(db: AsyncSession = Depends(get_db_session)) -> Dict[str, Any]:
+    """
+    List the registered engine profiles.
+
+    Returns:
+        Dictionary with every profile and the name of the default one
+        (empty when the Stockfish path setting is used)
+    """
+    engines = await crud_engines.get_engines(db)
+    settings = await crud_settings.get_all_settings(db)
+
+    return {
+        "engines": [crud_engines.engine_to_dict(engine) for engine in engines],
+        "default_engine": settings.get("default_engine") or ""
+    }
+
+
+@router.post("/api/engines")
+async def register_engine(
+    request: EngineRequest,
+    db: AsyncSession = Depends(get_db_session)
+) -> Dict[str, Any]:
+    """
+    Register an engine profile.
+
+    The engine is started once to detect its name ("id name") and to check
+    that it supports the given UCI options.
+
+    Args:
+        request: Name, optional label, binary path and UCI options of the profile
+
+    Returns:
+        Dictionary with the registered profile
+    """
+    if not request.name or not request.path:
+        raise HTTPException(status_code=400, detail="Name and path are required")
+
+    if await crud_engines.get_engine_by_name(db, request.name):
+        raise HTTPException(status_code=409, detail=f"Engine '{request.name}' already exists")
+
+    uci_options = request.uci_options or {}
+    identity = await _identify(request.path, uci_options)
+    engine = await crud_engines.create_engine(db, request.name, request.label, request.path, uci_options, identity)
+
+    return {
+        "success": True,
+        "engine": crud_engines.engine_to_dict(engine)
+    }
+
+
+@router.put("/api/engines/{name}")
+async def update_engine(
+    name: str,
+    request: EngineRequest,
+    db: AsyncSession = Depends(get_db_session)
+) -> Dict[str, Any]:
+    """
+    Change an engine profile.
+
+    A new path or new options are checked by starting the engine again,
+    which also updates the detected engine name. Analyses are then checked
+    for staleness, since they may have been made with the old binary.
+
+    Args:
+        name: Profile name
+        request: New label, path and/or UCI options
+
+    Returns:
+        Dictionary with the updated profile and the number of games marked stale
+    """
+    engine = await crud_engines.get_engine_by_name(db, name)
+
+    if not engine:
+        raise HTTPException(status_code=404, detail="Engine not found")
+
+    identity = None
+    if request.path is not None or request.uci_options is not None:
+        current = crud_engines.engine_to_dict(engine)
+        identity = await _identify(
+            request.path if request.path is not None else current["path"],
+            request.uci_options if request.uci_options is not None else current["uci_options"]
+        )
+
+    engine = await crud_engines.update_engine(db, engine, request.model_dump(exclude={"name"}), identity)
+    staleness = await mark_stale_analyses(db)
+
+    return {
+        "success": True,
+        "engine": crud_engines.engine_to_dict(engine),
+        "stale_games": staleness["stale"],
+        "restored_games": staleness["restored"]
+    }
+
+
+@router.delete("/api/engines/{name}")
+async def delete_engine(name: str, db: AsyncSession = Depends(get_db_session)) -> Dict[str, Any]:
+    """
+    Delete an engine profile that isn't the default engine.
+
+    Stored analyses keep the engine name and path they were made with.
+
+    Args:
+        name: Profile name
+
+    Returns:
+        Success message
+    """
+    engine = await crud_engines.get_engine_by_name(db, name)
+
+    if not engine:
+        raise HTTPException(status_code=404, detail="Engine not found")
+
+    settings = await crud_settings.get_all_settings(db)
+    if settings.get("default_engine") == name:
+        raise HTTPException(status_code=409, detail=f"Engine '{name}' is the default engine")
+
+    await crud_engines.delete_engine(db, engine)
+
+    return {
+        "success": True,
+        "message": f"Deleted engine '{name}'"
+    }
@@ -7,11 +7,19 @@ from datetime import datetime
 from ..db.database import get_db_session
 from ..crud import games as crud_games
 from ..db.models import Game
+from ..crud import analysis_jobs as crud_jobs
+from ..crud import analyses as crud_analyses
+from ..crud import analysis_presets as crud_presets
+from ..crud import engines as crud_engines
+from ..crud import settings as crud_settings
 from ..services.stockfish_service import (
-    analyze_game_async,
     get_game_analysis,
     has_game_analysis
 )
+from ..services.system_resources import validate_settings
+from ..services.analysis_diff import diff_analyses
+from ..services.analysis_scope import SCOPE_OPTIONS, validate_scope_options
+from ..services.analysis_queue import enqueue_analysis, worker_pool
 
 
 router = APIRouter()
@@ -27,16 +35,49 @@ class GameResponse(BaseModel):
     game_date: Optional[str] = None
     import_date: datetime
     analysis_status: str
-    has_analysis: bool = False  # Whether analysis file exists
-
-    @field_serializer('import_date')
-    def serialize_import_date(self, import_date: datetime, _info):
-        return import_date.isoformat() if import_date else None
+    analysis_error: Optional[str] = None
+    analysis_attempts: int = 0
+    analysis_queued_at: Optional[datetime] = None
+    analysis_started_at: Optional[datetime] = None
+    analysis_finished_at: Optional[datetime] = None
+    has_analysis: bool = False  # Whether an analysis is stored
+    white_accuracy: Optional[float] = None
+    black_accuracy: Optional[float] = None
+    eco: Optional[str] = None
+    opening_name: Optional[str] = None
+
+    @field_serializer('import_date', 'analysis_queued_at', 'analysis_started_at', 'analysis_finished_at')
+    def serialize_timestamp(self, timestamp: Optional[datetime], _info):
+        return timestamp.isoformat() if timestamp else None
 
     class Config:
         from_attributes = True
 
 
+# Stored setting each re-analysis option is validated as
+OVERRIDE_SETTING_KEYS = {
+    "depth": "analysis_depth",
+    "time_ms": "analysis_time_ms",
+    "multipv": "analysis_multipv",
+    "threads": "stockfish_threads",
+    "hash_mb": "stockfish_hash_mb"
+}
+
+
+class AnalyzeRequest(BaseModel):
+    reanalyze: bool = False  # Analyse again even if the game has an analysis
+    preset: Optional[str] = None  # Analysis preset; the options below override single values
+    engine: Optional[str] = None  # Engine profile instead of the default engine
+    scope: Optional[str] = None  # all, mine, range, critical or adaptive (see AnalysisScope)
+    from_ply: Optional[int] = None  # 0-based ply range for scope "range", inclusive
+    to_ply: Optional[int] = None
+    depth: Optional[int] = None
+    time_ms: Optional[int] = None
+    multipv: Optional[int] = None
+    threads: Optional[int] = None
+    hash_mb: Optional[int] = None
+
+
 class GamesListResponse(BaseModel):
     games: List[GameResponse]
     total: int
@@ -44,6 +85,19 @@ class GamesListResponse(BaseModel):
     limit: int
 
 
+def _analysis_state(game: Game, has_analysis: bool) -> Dict[str, Any]:
+    # Read-only: the stored status is only changed by the analysis queue
+    return {
+        "analysis_status": game.analysis_status or "queued",
+        "analysis_error": game.analysis_error,
+        "analysis_attempts": game.analysis_attempts or 0,
+        "analysis_queued_at": game.analysis_queued_at,
+        "analysis_started_at": game.analysis_started_at,
+        "analysis_finished_at": game.analysis_finished_at,
+        "has_analysis": has_analysis
+    }
+
+
 @router.get("/api/games", response_model=GamesListResponse)
 async def get_games(
     skip: int = 0,
@@ -51,21 +105,23 @@ async def get_games(
     date_from: Optional[str] = None,
     date_to: Optional[str] = None,
     status: Optional[str] = None,
+    opening: Optional[str] = None,
     sort_by: Optional[str] = 'date',
     sort_order: Optional[str] = 'desc',
     db: AsyncSession = Depends(get_db_session)
 ):
     """
     Get paginated list of games with optional filters and sorting.
-    Automatically detects if analysis files exist for each game.
+    Reports whether an analysis is stored for each game.
 
     Args:
         skip: Number of games to skip (for pagination)
         limit: Maximum number of games to return (max 1000)
         date_from: Filter games from this date (format: YYYY-MM-DD)
         date_to: Filter games to this date (format: YYYY-MM-DD)
-        status: Filter by analysis status (queued/analyzing/completed)
-        sort_by: Field to sort by (date/result/status)
+        status: Filter by analysis status (queued/analyzing/completed/failed/cancelled/stale)
+        opening: Filter by ECO code prefix or part of the opening name
+        sort_by: Field to sort by (date/result/status/opening)
         sort_order: Sort order (asc/desc)
 
     Returns:
@@ -85,6 +141,7 @@ async def get_games(
         date_from=date_from_db,
         date_to=date_to_db,
         status=status,
+        opening=opening,
         sort_by=sort_by,
         sort_order=sort_order
     )
@@ -92,10 +149,13 @@ async def get_games(
         db,
         date_from=date_from_db,
         date_to=date_to_db,
-        status=status
+        status=status,
+        opening=opening
     )
 
-    # Convert to response models and check for analysis files
+    # One query for the whole page instead of one per game
+    analysed = await crud_analyses.get_analysed_game_ids(db, [game.id for game in games])
+
     game_responses = []
     for game in games:
         game_dict = {
@@ -106,16 +166,13 @@ async def get_games(
             "result": game.result,
             "game_date": game.game_date,
             "import_date": game.import_date,
-            "analysis_status": game.analysis_status,
-            "has_analysis": has_game_analysis(game.id)
+            **_analysis_state(game, game.id in analysed),
+            "white_accuracy": game.white_accuracy,
+            "black_accuracy": game.black_accuracy,
+            "eco": game.eco,
+            "opening_name": game.opening_name
         }
 
-        # Auto-detect: if analysis file exists, status should be "completed"
-        if game_dict["has_analysis"] and game_dict["analysis_status"] != "completed":
-            game_dict["analysis_status"] = "completed"
-            # Update database status if it's different
-            await crud_games.update_game_analysis_status(db, game.id, "completed")
-
         game_responses.append(GameResponse(**game_dict))
 
     return GamesListResponse(
@@ -135,18 +192,25 @@ async def get_games_stats(db: AsyncSession = Depends(get_db_session)):
         Dictionary with game statistics
     """
     total = await crud_games.get_games_count(db)
-    queued = await crud_games.get_games_by_analysis_status(db, 'queued')
-    analyzing = await crud_games.get_games_by_analysis_status(db, 'analyzing')
-    completed = await crud_games.get_games_by_analysis_status(db, 'completed')
+    counts = await crud_games.get_analysis_status_counts(db)
 
     return {
         'total': total,
-        'queued': len(queued),
-        'analyzing': len(analyzing),
-        'completed': len(completed)
+        **counts
     }
 
 
+@router.get("/api/games/openings")
+async def get_games_openings(db: AsyncSession = Depends(get_db_session)) -> List[Dict[str, Any]]:
+    """
+    Get the openings played, with the number of games for each.
+
+    Returns:
+        List of openings (eco, opening_name, count), most played first
+    """
+    return await crud_games.get_opening_counts(db)
+
+
 @router.get("/api/games/{game_id}", response_model=GameResponse)
 async def get_game(
     game_id: int,
@@ -166,7 +230,6 @@ async def get_game(
     if not game:
         raise HTTPException(status_code=404, detail="Game not found")
 
-    # Create response with analysis detection
     game_dict = {
         "id": game.id,
         "chess_com_id": game.chess_com_id,
@@ -176,31 +239,37 @@ async def get_game(
         "result": game.result,
         "game_date": game.game_date,
         "import_date": game.import_date,
-        "analysis_status": game.analysis_status,
-        "has_analysis": has_game_analysis(game.id)
+        **_analysis_state(game, await has_game_analysis(db, game.id)),
+        "white_accuracy": game.white_accuracy,
+        "black_accuracy": game.black_accuracy,
+        "eco": game.eco,
+        "opening_name": game.opening_name
     }
 
-    # Auto-detect: if analysis file exists, status should be "completed"
-    if game_dict["has_analysis"] and game_dict["analysis_status"] != "completed":
-        game_dict["analysis_status"] = "completed"
-        await crud_games.update_game_analysis_status(db, game.id, "completed")
-
     return GameResponse(**game_dict)
 
 
 @router.post("/api/games/{game_id}/analyze")
 async def analyze_game(
     game_id: int,
+    request: Optional[AnalyzeRequest] = None,
     db: AsyncSession = Depends(get_db_session)
 ):
     """
-    Analyze a game using Stockfish and save results to JSON file.
+    Add a game to the background analysis queue.
+
+    Analysed games are only analysed again when reanalyze is set, a preset,
+    engine, scope or setting overrides are given; the result is stored as a new
+    analysis version. Without a preset, the default preset of the game's
+    time class is used.
 
     Args:
         game_id: Database ID of the game to analyze
+        request: Optional re-analysis flag, preset name, engine profile, scope
+            (with ply range) and overrides for depth, time_ms, multipv, threads and hash_mb
 
     Returns:
-        Dictionary with analysis status and results
+        Dictionary with queue status for the game
     """
     # Get the game from database
     game = await crud_games.get_game_by_id(db, game_id)
@@ -208,8 +277,43 @@ async def analyze_game(
     if not game:
         raise HTTPException(status_code=404, detail="Game not found")
 
+    request = request or AnalyzeRequest()
+    overrides = {
+        option: value
+        for option, value in request.model_dump(exclude={"reanalyze", "preset", "engine", *SCOPE_OPTIONS}).items()
+        if value is not None
+    }
+
+    if request.preset and not await crud_presets.get_preset_by_name(db, request.preset):
+        raise HTTPException(status_code=400, detail=f"Unknown analysis preset '{request.preset}'")
+    if request.engine and not await crud_engines.get_engine_by_name(db, request.engine):
+        raise HTTPException(status_code=400, detail=f"Unknown engine '{request.engine}'")
+
+    # Validate overrides with the same rules as the settings they replace
+    override_settings = {OVERRIDE_SETTING_KEYS[option]: value for option, value in overrides.items()}
+    _, errors = validate_settings(override_settings)
+
+    settings = await crud_settings.get_all_settings(db)
+    errors += validate_scope_options(request.scope, request.from_ply, request.to_ply, settings.get("chess_com_username"))
+
+    if errors:
+        raise HTTPException(status_code=400, detail={"message": "Invalid analysis options", "errors": errors})
+
+    if request.preset:
+        overrides["preset"] = request.preset
+    if request.engine:
+        overrides["engine"] = request.engine
+    if request.scope:
+        overrides.update({
+            option: getattr(request, option)
+            for option in SCOPE_OPTIONS
+            if getattr(request, option) is not None
+        })
+
+    reanalyze = request.reanalyze or bool(overrides)
+
     # Check if already analyzed
-    if has_game_analysis(game_id):
+    if not reanalyze and await has_game_analysis(db, game_id):
         return {
             "success": True,
             "message": "Game already analyzed",
@@ -217,42 +321,63 @@ async def analyze_game(
             "status": "already_completed"
         }
 
-    try:
-        # Update status to analyzing
-        await crud_games.update_game_analysis_status(db, game_id, "analyzing")
+    # Analysis requested for a single game jumps ahead of bulk jobs
+    await enqueue_analysis([game_id], priority=1, options=overrides if reanalyze else None)
+    job = await crud_jobs.get_active_job_for_game(db, game_id)
 
-        # Run analysis
-        result = await analyze_game_async(
-            game_id=game_id,
-            pgn_text=game.pgn,
-            db=db
-        )
+    return {
+        "success": True,
+        "message": "Game added to analysis queue",
+        "game_id": game_id,
+        "job_id": job.id if job else None,
+        "status": job.status if job else "queued"
+    }
+
+
+@router.post("/api/games/{game_id}/analyze/cancel")
+async def cancel_game_analysis(
+    game_id: int,
+    db: AsyncSession = Depends(get_db_session)
+):
+    """
+    Cancel the queued or running analysis of a game.
 
-        # Update status to completed
-        await crud_games.update_game_analysis_status(db, game_id, "completed")
+    A running analysis stops its engine search; the moves analysed so far
+    are kept and reused when the game is analysed again.
 
+    Args:
+        game_id: Database ID of the game
+
+    Returns:
+        Dictionary with cancel stats for the game
+    """
+    game = await crud_games.get_game_by_id(db, game_id)
+
+    if not game:
+        raise HTTPException(status_code=404, detail="Game not found")
+
+    result = await worker_pool.cancel([game_id])
+
+    if not result["cancelled"] and not result["stopping"]:
         return {
-            "success": True,
-            "message": "Game analyzed successfully",
+            "success": False,
+            "message": "Game is not being analyzed",
             "game_id": game_id,
-            "total_moves": result["total_moves"],
-            "analysis_file": result["analysis_file"],
-            "status": "completed"
+            **result
         }
 
-    except Exception as e:
-        # Reset status to queued on error
-        await crud_games.update_game_analysis_status(db, game_id, "queued")
-
-        raise HTTPException(
-            status_code=500,
-            detail=f"Analysis failed: {str(e)}"
-        )
+    return {
+        "success": True,
+        "message": "Analysis stopping" if result["stopping"] else "Analysis removed from queue",
+        "game_id": game_id,
+        **result
+    }
 
 
 @router.get("/api/games/{game_id}/analysis")
 async def get_analysis(
     game_id: int,
+    version: Optional[int] = None,
     db: AsyncSession = Depends(get_db_session)
 ):
     """
@@ -260,6 +385,7 @@ async def get_analysis(
 
     Args:
         game_id: Database ID of the game
+        version: Analysis version (the latest if omitted)
 
     Returns:
         Analysis data if available, 404 if not found
@@ -270,8 +396,7 @@ async def get_analysis(
     if not game:
         raise HTTPException(status_code=404, detail="Game not found")
 
-    # Get analysis from JSON file
-    analysis = get_game_analysis(game_id)
+    analysis = await get_game_analysis(db, game_id, version)
 
     if not analysis:
         raise HTTPException(
@@ -282,5 +407,70 @@ async def get_analysis(
     return {
         "success": True,
         "game_id": game_id,
+        "player_stats": analysis["player_stats"],
         "analysis": analysis
     }
+
+
+@router.get("/api/games/{game_id}/analyses")
+async def get_analysis_versions(
+    game_id: int,
+    db: AsyncSession = Depends(get_db_session)
+):
+    """
+    List all analysis versions of a game, newest first.
+
+    Args:
+        game_id: Database ID of the game
+
+    Returns:
+        Dictionary with the engine and search settings of every version
+    """
+    game = await crud_games.get_game_by_id(db, game_id)
+
+    if not game:
+        raise HTTPException(status_code=404, detail="Game not found")
+
+    versions = await crud_analyses.get_analysis_versions(db, game_id)
+
+    return {
+        "success": True,
+        "game_id": game_id,
+        "versions": [crud_analyses.analysis_version_info(analysis) for analysis in versions]
+    }
+
+
+@router.get("/api/games/{game_id}/analyses/diff")
+async def diff_analysis_versions(
+    game_id: int,
+    base: int,
+    other: int,
+    db: AsyncSession = Depends(get_db_session)
+):
+    """
+    Compare two analysis versions of a game move by move.
+
+    Args:
+        game_id: Database ID of the game
+        base: Version to compare from
+        other: Version to compare to
+
+    Returns:
+        Per-move classifications, scores and best moves of both versions
+    """
+    game = await crud_games.get_game_by_id(db, game_id)
+
+    if not game:
+        raise HTTPException(status_code=404, detail="Game not found")
+
+    base_analysis = await get_game_analysis(db, game_id, base)
+    other_analysis = await get_game_analysis(db, game_id, other)
+
+    if not base_analysis or not other_analysis:
+        raise HTTPException(status_code=404, detail="Analysis version not found")
+
+    return {
+        "success": True,
+        "game_id": game_id,
+        **diff_analyses(base_analysis, other_analysis)
+    }
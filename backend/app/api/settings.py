@@ -4,10 +4,19 @@ from typing import Dict, Any, List
 
 from ..db.database import get_db_session
 from ..crud import settings as crud_settings
+from ..crud import analysis_presets as crud_presets
+from ..crud import engines as crud_engines
 from ..services.system_resources import validate_settings
+from ..services.analysis_queue import worker_pool
+from ..services.engine_pool import engine_pool
+from ..services.analysis_staleness import STALENESS_SETTINGS, mark_stale_analyses
+from ..services.analysis_presets import TIME_CLASSES
 
 router = APIRouter()
 
+# Settings that require restarting engine processes
+ENGINE_SETTINGS = {"stockfish_path", "stockfish_threads", "stockfish_hash_mb", "syzygy_path", "syzygy_probe_limit"}
+
 @router.get("/api/settings", response_model=Dict[str, Any])
 async def read_settings(db: AsyncSession = Depends(get_db_session)):
     settings = await crud_settings.get_all_settings(db)
@@ -21,6 +30,19 @@ async def write_settings(
     # Validate settings before saving
     is_valid, errors = validate_settings(settings_data)
 
+    # Time-class defaults must name an existing preset (empty uses the analysis settings)
+    for time_class in TIME_CLASSES:
+        preset_name = settings_data.get(f"preset_{time_class}")
+        if preset_name and not await crud_presets.get_preset_by_name(db, preset_name):
+            is_valid = False
+            errors.append(f"Unknown analysis preset '{preset_name}' for {time_class} games")
+
+    # The default engine must be a registered profile (empty uses stockfish_path)
+    default_engine = settings_data.get("default_engine")
+    if default_engine and not await crud_engines.get_engine_by_name(db, default_engine):
+        is_valid = False
+        errors.append(f"Unknown engine '{default_engine}'")
+
     if not is_valid:
         raise HTTPException(
             status_code=400,
@@ -31,10 +53,34 @@ async def write_settings(
         )
 
     try:
+        previous = await crud_settings.get_all_settings(db)
+        changed_keys = {
+            key for key, value in settings_data.items()
+            if str(value) != str(previous.get(key))
+        }
+
         updated_keys = await crud_settings.update_settings(db, settings_data)
+
+        # Apply a new worker count without restarting the backend
+        if "analysis_workers" in changed_keys:
+            worker_pool.resize(int(settings_data["analysis_workers"]))
+
+        # Restart pooled engines whose process options changed
+        if "engine_pool_size" in changed_keys:
+            await engine_pool.resize(int(settings_data["engine_pool_size"]))
+        if ENGINE_SETTINGS & changed_keys:
+            await engine_pool.recycle()
+
+        # Flag analyses made with a weaker configuration than the new one
+        staleness = {"stale": 0, "restored": 0}
+        if STALENESS_SETTINGS & changed_keys:
+            staleness = await mark_stale_analyses(db)
+
         return {
             "status": "success",
-            "updated_settings": updated_keys
+            "updated_settings": updated_keys,
+            "stale_games": staleness["stale"],
+            "restored_games": staleness["restored"]
         }
     except Exception as e:
         raise HTTPException(
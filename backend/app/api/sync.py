@@ -7,6 +7,7 @@ from ..db.database import get_db_session, async_session
 from ..crud import settings as crud_settings
 from ..crud import games as crud_games
 from ..services.chess_com import ChessComAPI
+from ..services.openings import classify_opening
 
 
 router = APIRouter()
@@ -57,6 +58,7 @@ async def sync_games_background(username: str, limit_months: Optional[int] = Non
         for game in all_games:
             try:
                 game_data = chess_com_client.extract_game_data(game)
+                game_data.update(classify_opening(game_data['pgn']))
                 games_data.append(game_data)
             except Exception as e:
                 print(f"Error extracting game data: {str(e)}")
@@ -5,6 +5,8 @@ from typing import Dict, Any
 from ..db.database import get_db_session
 from ..crud import settings as crud_settings
 from ..services.system_resources import get_system_resources
+from ..services.engine_pool import engine_pool
+from ..services.position_cache import get_cache_stats
 
 
 router = APIRouter()
@@ -22,6 +24,8 @@ async def get_system_resources_endpoint(
         - cpu: CPU information (cores, recommended threads)
         - memory: Memory information (total, available, recommended hash)
         - stockfish: Stockfish binary information (path, validity)
+        - engine_pool: Pooled engine usage (size, idle, in use)
+        - position_cache: Position cache size and hit rate
     """
     # Get current Stockfish path from settings
     settings = await crud_settings.get_all_settings(db)
@@ -29,5 +33,7 @@ async def get_system_resources_endpoint(
 
     # Get system resources with Stockfish validation
     resources = get_system_resources(stockfish_path)
+    resources["engine_pool"] = engine_pool.stats()
+    resources["position_cache"] = await get_cache_stats(db)
 
     return resources
@@ -0,0 +1,250 @@
+from sqlalchemy.ext.asyncio import AsyncSession
+from sqlalchemy.future import select
+from sqlalchemy import func
+from ..db.models import Analysis, AnalysedPosition
+from typing import List, Dict, Any, Optional, Set
+import json
+
+
+async def get_analysis_for_game(
+    db: AsyncSession,
+    game_id: int,
+    version: Optional[int] = None
+) -> Optional[Analysis]:
+    """
+    Get a stored analysis of a game.
+
+    Args:
+        db: Database session
+        game_id: Database ID of the game
+        version: Analysis version (the latest if None)
+
+    Returns:
+        Analysis object if found, None otherwise
+    """
+    query = select(Analysis).where(Analysis.game_id == game_id)
+
+    if version is not None:
+        query = query.where(Analysis.version == version)
+    else:
+        query = query.order_by(Analysis.version.desc()).limit(1)
+
+    result = await db.execute(query)
+    return result.scalars().first()
+
+
+async def get_analysis_versions(db: AsyncSession, game_id: int) -> List[Analysis]:
+    """
+    Get all analyses of a game, newest first.
+
+    Args:
+        db: Database session
+        game_id: Database ID of the game
+
+    Returns:
+        List of Analysis objects
+    """
+    result = await db.execute(
+        select(Analysis)
+        .where(Analysis.game_id == game_id)
+        .order_by(Analysis.version.desc())
+    )
+    return result.scalars().all()
+
+
+async def get_positions(db: AsyncSession, analysis_id: int) -> List[AnalysedPosition]:
+    """
+    Get the analysed positions of an analysis in move order.
+
+    Args:
+        db: Database session
+        analysis_id: Database ID of the analysis
+
+    Returns:
+        List of AnalysedPosition objects
+    """
+    result = await db.execute(
+        select(AnalysedPosition)
+        .where(AnalysedPosition.analysis_id == analysis_id)
+        .order_by(AnalysedPosition.ply.asc())
+    )
+    return result.scalars().all()
+
+
+async def get_analysed_game_ids(db: AsyncSession, game_ids: Optional[List[int]] = None) -> Set[int]:
+    """
+    Find which games have a stored analysis.
+
+    Args:
+        db: Database session
+        game_ids: Games to check (all games if None)
+
+    Returns:
+        Set of game IDs that have an analysis
+    """
+    query = select(Analysis.game_id).distinct()
+    if game_ids is not None:
+        if not game_ids:
+            return set()
+        query = query.where(Analysis.game_id.in_(game_ids))
+
+    result = await db.execute(query)
+    return set(result.scalars().all())
+
+
+async def get_latest_analyses(db: AsyncSession, game_ids: Optional[List[int]] = None) -> Dict[int, Analysis]:
+    """
+    Get the current (highest) analysis version of several games at once.
+
+    Args:
+        db: Database session
+        game_ids: Games to look up (all games if None)
+
+    Returns:
+        Dictionary mapping game ID to its latest Analysis
+    """
+    latest = select(Analysis.game_id, func.max(Analysis.version).label("version")).group_by(Analysis.game_id)
+    if game_ids is not None:
+        if not game_ids:
+            return {}
+        latest = latest.where(Analysis.game_id.in_(game_ids))
+    latest = latest.subquery()
+
+    result = await db.execute(
+        select(Analysis).join(
+            latest,
+            (Analysis.game_id == latest.c.game_id) & (Analysis.version == latest.c.version)
+        )
+    )
+    return {analysis.game_id: analysis for analysis in result.scalars().all()}
+
+
+def _position_row(ply: int, move: Dict[str, Any]) -> Dict[str, Any]:
+    analysis = move.get("analysis") or {}
+    return {
+        "ply": ply,
+        "move_number": move.get("move_number"),
+        "move_san": move.get("move"),
+        "move_uci": move.get("uci"),
+        "fen": move.get("fen_before") or analysis.get("fen"),
+        "fen_after": move.get("fen_after"),
+        "score_type": analysis.get("score_type"),
+        "score_value": analysis.get("score_value"),
+        "best_move": analysis.get("best_move"),
+        "pv": " ".join(analysis.get("pv") or []),
+        "depth": analysis.get("depth"),
+        "classification": move.get("classification"),
+        "cp_loss": move.get("cp_loss"),
+        "accuracy": move.get("accuracy"),
+        "book": bool(move.get("book")),
+        "data": json.dumps(move)
+    }
+
+
+async def save_analysis(db: AsyncSession, game_id: int, analysis_result: Dict[str, Any]) -> Analysis:
+    """
+    Store a game analysis as the game's next version. Earlier versions are kept.
+
+    Args:
+        db: Database session
+        game_id: Database ID of the game
+        analysis_result: Result of StockfishAnalyzer.analyze_game
+
+    Returns:
+        The created Analysis object
+    """
+    result = await db.execute(
+        select(func.max(Analysis.version)).where(Analysis.game_id == game_id)
+    )
+    version = (result.scalar() or 0) + 1
+
+    settings = analysis_result.get("analysis_settings") or {}
+    player_stats = analysis_result.get("player_stats") or {}
+    analysis = Analysis(
+        game_id=game_id,
+        version=version,
+        engine_name=settings.get("engine_name"),
+        engine_path=settings.get("engine_path"),
+        engine_profile=settings.get("engine_profile"),
+        preset=settings.get("preset"),
+        scope=(settings.get("scope") or {}).get("mode", "all"),
+        settings_fingerprint=settings.get("fingerprint"),
+        depth=settings.get("depth"),
+        time_ms=settings.get("time_ms"),
+        threads=settings.get("threads"),
+        hash_mb=settings.get("hash_mb"),
+        multipv=settings.get("multipv"),
+        total_moves=analysis_result.get("total_moves"),
+        final_fen=analysis_result.get("final_fen"),
+        white_accuracy=(player_stats.get("white") or {}).get("accuracy"),
+        black_accuracy=(player_stats.get("black") or {}).get("accuracy"),
+        game_info=json.dumps(analysis_result.get("game_info")),
+        settings=json.dumps(settings),
+        final_analysis=json.dumps(analysis_result.get("final_analysis")),
+        classification_summary=json.dumps(analysis_result.get("classification_summary")),
+        player_stats=json.dumps(player_stats)
+    )
+    db.add(analysis)
+    await db.flush()
+
+    for ply, move in enumerate(analysis_result.get("moves", [])):
+        db.add(AnalysedPosition(analysis_id=analysis.id, **_position_row(ply, move)))
+
+    await db.commit()
+    await db.refresh(analysis)
+    return analysis
+
+
+def analysis_version_info(analysis: Analysis) -> Dict[str, Any]:
+    """
+    Summarise an analysis version without its positions.
+
+    Args:
+        analysis: Stored analysis
+
+    Returns:
+        Dictionary with version, creation time, engine, search settings and accuracy
+    """
+    return {
+        "id": analysis.id,
+        "version": analysis.version,
+        "created_at": analysis.created_at.isoformat() if analysis.created_at else None,
+        "engine_name": analysis.engine_name,
+        "engine_path": analysis.engine_path,
+        "engine_profile": analysis.engine_profile,
+        "preset": analysis.preset,
+        "scope": analysis.scope or "all",
+        "fingerprint": analysis.settings_fingerprint,
+        "depth": analysis.depth,
+        "time_ms": analysis.time_ms,
+        "threads": analysis.threads,
+        "hash_mb": analysis.hash_mb,
+        "multipv": analysis.multipv,
+        "limit": (json.loads(analysis.settings) if analysis.settings else {}).get("limit"),
+        "white_accuracy": analysis.white_accuracy,
+        "black_accuracy": analysis.black_accuracy
+    }
+
+
+def analysis_to_dict(analysis: Analysis, positions: List[AnalysedPosition]) -> Dict[str, Any]:
+    """
+    Rebuild the analysis dictionary produced by StockfishAnalyzer.analyze_game.
+
+    Args:
+        analysis: Stored analysis
+        positions: Its analysed positions in move order
+
+    Returns:
+        Analysis dictionary as returned by the analysis endpoints
+    """
+    return {
+        "version": analysis_version_info(analysis),
+        "game_info": json.loads(analysis.game_info) if analysis.game_info else {},
+        "analysis_settings": json.loads(analysis.settings) if analysis.settings else {},
+        "moves": [json.loads(position.data) for position in positions],
+        "total_moves": analysis.total_moves,
+        "final_fen": analysis.final_fen,
+        "final_analysis": json.loads(analysis.final_analysis) if analysis.final_analysis else None,
+        "classification_summary": json.loads(analysis.classification_summary) if analysis.classification_summary else None,
+        "player_stats": json.loads(analysis.player_stats) if analysis.player_stats else None
+    }
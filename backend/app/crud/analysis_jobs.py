@@ -0,0 +1,303 @@
+from sqlalchemy.ext.asyncio import AsyncSession
+from sqlalchemy.future import select
+from sqlalchemy import func, update
+from ..db.models import AnalysisJob, Game
+from .games import apply_analysis_status, InvalidStatusTransition
+from .analyses import get_analysed_game_ids
+from typing import List, Dict, Any, Optional
+from datetime import datetime
+import json
+
+
+ACTIVE_JOB_STATUSES = ('queued', 'running')
+
+
+async def get_job_by_id(db: AsyncSession, job_id: int) -> Optional[AnalysisJob]:
+    """
+    Get an analysis job by its database ID.
+
+    Args:
+        db: Database session
+        job_id: Database ID of the job
+
+    Returns:
+        AnalysisJob object if found, None otherwise
+    """
+    result = await db.execute(
+        select(AnalysisJob).where(AnalysisJob.id == job_id)
+    )
+    return result.scalars().first()
+
+
+async def get_active_job_for_game(db: AsyncSession, game_id: int) -> Optional[AnalysisJob]:
+    """
+    Get the queued or running job for a game, if any.
+
+    Args:
+        db: Database session
+        game_id: Database ID of the game
+
+    Returns:
+        AnalysisJob object if the game is waiting for or undergoing analysis
+    """
+    result = await db.execute(
+        select(AnalysisJob).where(
+            AnalysisJob.game_id == game_id,
+            AnalysisJob.status.in_(ACTIVE_JOB_STATUSES)
+        )
+    )
+    return result.scalars().first()
+
+
+async def enqueue_games(
+    db: AsyncSession,
+    game_ids: List[int],
+    priority: int = 0,
+    options: Optional[Dict[str, Any]] = None
+) -> Dict[str, int]:
+    """
+    Add analysis jobs for games, skipping games that already have an active job.
+    The games are moved to the 'queued' state.
+
+    Args:
+        db: Database session
+        game_ids: Database IDs of the games to analyze
+        priority: Higher priority jobs are picked up first
+        options: Setting overrides stored on the jobs (marks them as re-analysis)
+
+    Returns:
+        Dictionary with stats: {'enqueued': int, 'skipped': int, 'total': int}
+    """
+    result = await db.execute(
+        select(AnalysisJob.game_id).where(
+            AnalysisJob.game_id.in_(game_ids),
+            AnalysisJob.status.in_(ACTIVE_JOB_STATUSES)
+        )
+    )
+    already_active = set(result.scalars().all())
+
+    result = await db.execute(select(Game).where(Game.id.in_(game_ids)))
+    games = {game.id: game for game in result.scalars().all()}
+
+    enqueued = 0
+    for game_id in game_ids:
+        if game_id in already_active or game_id not in games:
+            continue
+        try:
+            apply_analysis_status(games[game_id], 'queued')
+        except InvalidStatusTransition:
+            continue
+        db.add(AnalysisJob(
+            game_id=game_id,
+            status='queued',
+            priority=priority,
+            options=json.dumps(options) if options is not None else None
+        ))
+        already_active.add(game_id)
+        enqueued += 1
+
+    await db.commit()
+
+    return {
+        'enqueued': enqueued,
+        'skipped': len(game_ids) - enqueued,
+        'total': len(game_ids)
+    }
+
+
+async def claim_next_job(db: AsyncSession) -> Optional[AnalysisJob]:
+    """
+    Atomically take the next queued job and mark it as running.
+
+    The status check in the UPDATE makes sure two workers never
+    claim the same job.
+
+    Args:
+        db: Database session
+
+    Returns:
+        The claimed AnalysisJob, or None if the queue is empty
+    """
+    while True:
+        result = await db.execute(
+            select(AnalysisJob.id)
+            .where(AnalysisJob.status == 'queued')
+            .order_by(AnalysisJob.priority.desc(), AnalysisJob.id.asc())
+            .limit(1)
+        )
+        job_id = result.scalar()
+
+        if job_id is None:
+            return None
+
+        claimed = await db.execute(
+            update(AnalysisJob)
+            .where(AnalysisJob.id == job_id, AnalysisJob.status == 'queued')
+            .values(
+                status='running',
+                started_at=datetime.now(),
+                attempts=AnalysisJob.attempts + 1
+            )
+        )
+        await db.commit()
+
+        if claimed.rowcount == 1:
+            return await get_job_by_id(db, job_id)
+
+
+async def finish_job(
+    db: AsyncSession,
+    job_id: int,
+    status: str,
+    error: Optional[str] = None
+) -> Optional[AnalysisJob]:
+    """
+    Mark a job as finished.
+
+    Args:
+        db: Database session
+        job_id: Database ID of the job
+        status: Final status ('completed', 'failed' or 'cancelled')
+        error: Optional error message for failed jobs
+
+    Returns:
+        Updated AnalysisJob object if found, None otherwise
+    """
+    job = await get_job_by_id(db, job_id)
+
+    if not job:
+        return None
+
+    job.status = status
+    job.error = error
+    job.finished_at = datetime.now()
+
+    await db.commit()
+    await db.refresh(job)
+    return job
+
+
+async def requeue_job(db: AsyncSession, job_id: int, error: Optional[str] = None) -> Optional[AnalysisJob]:
+    """
+    Put a failed job back into the queue for another attempt.
+
+    Args:
+        db: Database session
+        job_id: Database ID of the job
+        error: Error message of the failed attempt
+
+    Returns:
+        Updated AnalysisJob object if found, None otherwise
+    """
+    job = await get_job_by_id(db, job_id)
+
+    if not job:
+        return None
+
+    job.status = 'queued'
+    job.error = error
+    job.started_at = None
+
+    await db.commit()
+    await db.refresh(job)
+    return job
+
+
+async def cancel_queued_jobs(db: AsyncSession, game_ids: Optional[List[int]] = None) -> List[AnalysisJob]:
+    """
+    Mark queued jobs and their games as cancelled so no worker picks them up.
+
+    Games with a stored analysis go back to 'completed' instead, since a
+    cancelled re-analysis leaves that analysis valid. Running jobs are not
+    touched; they are stopped by the worker pool.
+
+    Args:
+        db: Database session
+        game_ids: Only cancel jobs for these games (all queued jobs if None)
+
+    Returns:
+        List of cancelled AnalysisJob objects
+    """
+    query = select(AnalysisJob).where(AnalysisJob.status == 'queued')
+    if game_ids is not None:
+        query = query.where(AnalysisJob.game_id.in_(game_ids))
+
+    result = await db.execute(query)
+    jobs = result.scalars().all()
+
+    job_game_ids = [job.game_id for job in jobs]
+    analysed = await get_analysed_game_ids(db, job_game_ids)
+    result = await db.execute(select(Game).where(Game.id.in_(job_game_ids)))
+    for game in result.scalars().all():
+        if game.analysis_status == 'queued':
+            apply_analysis_status(game, 'completed' if game.id in analysed else 'cancelled')
+
+    for job in jobs:
+        job.status = 'cancelled'
+        job.finished_at = datetime.now()
+
+    await db.commit()
+    return jobs
+
+
+async def reclaim_interrupted_jobs(db: AsyncSession) -> int:
+    """
+    Put jobs that were running when the backend stopped back into the queue.
+
+    Games left in 'analyzing' by those jobs are reset to 'queued' as well.
+
+    Args:
+        db: Database session
+
+    Returns:
+        Number of reclaimed jobs
+    """
+    result = await db.execute(
+        update(AnalysisJob)
+        .where(AnalysisJob.status == 'running')
+        .values(status='queued', started_at=None)
+    )
+    await db.execute(
+        update(Game)
+        .where(Game.analysis_status == 'analyzing')
+        .values(analysis_status='queued')
+    )
+    await db.commit()
+    return result.rowcount
+
+
+async def get_queue_stats(db: AsyncSession) -> Dict[str, int]:
+    """
+    Count analysis jobs by status.
+
+    Args:
+        db: Database session
+
+    Returns:
+        Dictionary mapping each status to its job count
+    """
+    result = await db.execute(
+        select(AnalysisJob.status, func.count(AnalysisJob.id)).group_by(AnalysisJob.status)
+    )
+    stats = {'queued': 0, 'running': 0, 'completed': 0, 'failed': 0, 'cancelled': 0}
+    for status, count in result.all():
+        stats[status] = count
+    return stats
+
+
+async def get_active_jobs(db: AsyncSession) -> List[AnalysisJob]:
+    """
+    Get all queued and running jobs in the order they will be processed.
+
+    Args:
+        db: Database session
+
+    Returns:
+        List of AnalysisJob objects
+    """
+    result = await db.execute(
+        select(AnalysisJob)
+        .where(AnalysisJob.status.in_(ACTIVE_JOB_STATUSES))
+        .order_by(AnalysisJob.priority.desc(), AnalysisJob.id.asc())
+    )
+    return result.scalars().all()
@@ -0,0 +1,107 @@
+from sqlalchemy.ext.asyncio import AsyncSession
+from sqlalchemy.future import select
+from ..db.models import AnalysisPreset
+from typing import List, Dict, Any, Optional
+
+
+async def get_presets(db: AsyncSession) -> List[AnalysisPreset]:
+    """
+    Get all analysis presets, fastest first.
+
+    Args:
+        db: Database session
+
+    Returns:
+        List of AnalysisPreset objects
+    """
+    result = await db.execute(
+        select(AnalysisPreset).order_by(AnalysisPreset.time_ms.asc(), AnalysisPreset.depth.asc())
+    )
+    return result.scalars().all()
+
+
+async def get_preset_by_name(db: AsyncSession, name: str) -> Optional[AnalysisPreset]:
+    """
+    Get an analysis preset by its name.
+
+    Args:
+        db: Database session
+        name: Preset name
+
+    Returns:
+        AnalysisPreset object if found, None otherwise
+    """
+    result = await db.execute(select(AnalysisPreset).where(AnalysisPreset.name == name))
+    return result.scalars().first()
+
+
+async def create_preset(db: AsyncSession, name: str, depth: int, time_ms: int, multipv: int) -> AnalysisPreset:
+    """
+    Create an analysis preset.
+
+    Args:
+        db: Database session
+        name: Unique preset name
+        depth: Search depth
+        time_ms: Time per move in milliseconds
+        multipv: Number of candidate lines
+
+    Returns:
+        The created AnalysisPreset object
+    """
+    preset = AnalysisPreset(name=name, depth=depth, time_ms=time_ms, multipv=multipv)
+    db.add(preset)
+    await db.commit()
+    await db.refresh(preset)
+    return preset
+
+
+async def update_preset(db: AsyncSession, preset: AnalysisPreset, values: Dict[str, Any]) -> AnalysisPreset:
+    """
+    Change the search settings of a preset.
+
+    Args:
+        db: Database session
+        preset: Preset to update
+        values: New depth, time_ms and/or multipv
+
+    Returns:
+        The updated AnalysisPreset object
+    """
+    for key in ("depth", "time_ms", "multipv"):
+        if values.get(key) is not None:
+            setattr(preset, key, values[key])
+
+    await db.commit()
+    await db.refresh(preset)
+    return preset
+
+
+def preset_to_dict(preset: AnalysisPreset) -> Dict[str, Any]:
+    """
+    Convert a preset to a dictionary for the API.
+
+    Args:
+        preset: Analysis preset
+
+    Returns:
+        Dictionary with name, depth, time_ms and multipv
+    """
+    return {
+        "name": preset.name,
+        "depth": preset.depth,
+        "time_ms": preset.time_ms,
+        "multipv": preset.multipv
+    }
+
+
+async def delete_preset(db: AsyncSession, preset: AnalysisPreset):
+    """
+    Delete an analysis preset.
+
+    Args:
+        db: Database session
+        preset: Preset to delete
+    """
+    await db.delete(preset)
+    await db.commit()
@@ -0,0 +1,135 @@
+import json
+from sqlalchemy.ext.asyncio import AsyncSession
+from sqlalchemy.future import select
+from ..db.models import EngineProfile
+from typing import List, Dict, Any, Optional
+
+
+async def get_engines(db: AsyncSession) -> List[EngineProfile]:
+    """
+    Get all engine profiles, in registration order.
+
+    Args:
+        db: Database session
+
+    Returns:
+        List of EngineProfile objects
+    """
+    result = await db.execute(select(EngineProfile).order_by(EngineProfile.id.asc()))
+    return result.scalars().all()
+
+
+async def get_engine_by_name(db: AsyncSession, name: str) -> Optional[EngineProfile]:
+    """
+    Get an engine profile by its name.
+
+    Args:
+        db: Database session
+        name: Profile name
+
+    Returns:
+        EngineProfile object if found, None otherwise
+    """
+    result = await db.execute(select(EngineProfile).where(EngineProfile.name == name))
+    return result.scalars().first()
+
+
+async def create_engine(
+    db: AsyncSession,
+    name: str,
+    label: Optional[str],
+    path: str,
+    uci_options: Dict[str, Any],
+    identity: Dict[str, Optional[str]]
+) -> EngineProfile:
+    """
+    Register an engine profile.
+
+    Args:
+        db: Database session
+        name: Unique profile name
+        label: Display name (the name is shown if empty)
+        path: Path of the engine binary
+        uci_options: UCI options by name
+        identity: "id_name" and "id_author" the engine reported
+
+    Returns:
+        The created EngineProfile object
+    """
+    engine = EngineProfile(
+        name=name,
+        label=label,
+        path=path,
+        uci_options=json.dumps(uci_options),
+        id_name=identity.get("id_name"),
+        id_author=identity.get("id_author")
+    )
+    db.add(engine)
+    await db.commit()
+    await db.refresh(engine)
+    return engine
+
+
+async def update_engine(
+    db: AsyncSession,
+    engine: EngineProfile,
+    values: Dict[str, Any],
+    identity: Optional[Dict[str, Optional[str]]] = None
+) -> EngineProfile:
+    """
+    Change an engine profile.
+
+    Args:
+        db: Database session
+        engine: Profile to update
+        values: New label, path and/or uci_options
+        identity: Newly detected "id_name" and "id_author" (None keeps the stored identity)
+
+    Returns:
+        The updated EngineProfile object
+    """
+    if values.get("label") is not None:
+        engine.label = values["label"]
+    if values.get("path") is not None:
+        engine.path = values["path"]
+    if values.get("uci_options") is not None:
+        engine.uci_options = json.dumps(values["uci_options"])
+    if identity is not None:
+        engine.id_name = identity.get("id_name")
+        engine.id_author = identity.get("id_author")
+
+    await db.commit()
+    await db.refresh(engine)
+    return engine
+
+
+def engine_to_dict(engine: EngineProfile) -> Dict[str, Any]:
+    """
+    Convert an engine profile to a dictionary for the API and the analyzer.
+
+    Args:
+        engine: Engine profile
+
+    Returns:
+        Dictionary with name, label, path, uci_options, id_name and id_author
+    """
+    return {
+        "name": engine.name,
+        "label": engine.label or engine.name,
+        "path": engine.path,
+        "uci_options": json.loads(engine.uci_options) if engine.uci_options else {},
+        "id_name": engine.id_name,
+        "id_author": engine.id_author
+    }
+
+
+async def delete_engine(db: AsyncSession, engine: EngineProfile):
+    """
+    Delete an engine profile.
+
+    Args:
+        db: Database session
+        engine: Profile to delete
+    """
+    await db.delete(engine)
+    await db.commit()
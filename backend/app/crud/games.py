@@ -1,8 +1,81 @@
 from sqlalchemy.ext.asyncio import AsyncSession
 from sqlalchemy.future import select
-from sqlalchemy import func
+from sqlalchemy import func, or_
 from ..db.models import Game
 from typing import List, Dict, Any, Optional
+from datetime import datetime
+
+
+# Analysis states and the states each one may move to:
+# queued -> analyzing -> completed / failed / cancelled, with retries going
+# back to queued; completed analyses become stale when the engine or search
+# settings get stronger, and completed again if the settings are reverted.
+# A re-analysis that is cancelled or fails returns to completed (or stale).
+ANALYSIS_STATUS_TRANSITIONS = {
+    'queued': {'queued', 'analyzing', 'completed', 'cancelled'},
+    'analyzing': {'queued', 'completed', 'failed', 'cancelled'},
+    'completed': {'queued', 'stale'},
+    'failed': {'queued'},
+    'cancelled': {'queued'},
+    'stale': {'queued', 'completed'},
+}
+
+ANALYSIS_STATUSES = tuple(ANALYSIS_STATUS_TRANSITIONS)
+
+
+class InvalidStatusTransition(ValueError):
+    """Raised when a game's analysis status can't move to the requested state."""
+
+
+def apply_analysis_status(
+    game: Game,
+    status: str,
+    error: Optional[str] = None,
+    force: bool = False
+):
+    """
+    Move a game to a new analysis state without committing.
+
+    Timestamps, the attempt counter and the error message are kept in
+    step with the state.
+
+    Args:
+        game: Game to update
+        status: New analysis status
+        error: Error message for 'failed', or of the failed attempt when re-queued
+        force: Skip the transition check (for repairs at startup)
+
+    Raises:
+        InvalidStatusTransition: If the transition is not allowed
+    """
+    current = game.analysis_status or 'queued'
+    if status not in ANALYSIS_STATUS_TRANSITIONS:
+        raise InvalidStatusTransition(f"Unknown analysis status '{status}'")
+    if not force and status not in ANALYSIS_STATUS_TRANSITIONS.get(current, ()):
+        raise InvalidStatusTransition(f"Game {game.id} can't go from '{current}' to '{status}'")
+
+    now = datetime.now()
+
+    if status == 'queued':
+        if current != 'analyzing':
+            # Queued again by the user - start counting attempts afresh
+            game.analysis_attempts = 0
+        game.analysis_queued_at = now
+        game.analysis_error = error
+    elif status == 'analyzing':
+        game.analysis_attempts = (game.analysis_attempts or 0) + 1
+        game.analysis_started_at = now
+        game.analysis_finished_at = None
+    elif status in ('completed', 'failed', 'cancelled') and current != 'stale':
+        game.analysis_finished_at = now
+        game.analysis_error = error if status == 'failed' else None
+
+    game.analysis_status = status
+
+
+def _opening_filter(opening: str):
+    # ECO codes are matched by prefix so 'B' or 'B9' select a whole group
+    return or_(Game.eco.like(f"{opening}%"), Game.opening_name.ilike(f"%{opening}%"))
 
 
 async def get_game_by_id(db: AsyncSession, game_id: int) -> Optional[Game]:
@@ -57,6 +130,8 @@ async def create_game(db: AsyncSession, game_data: Dict[str, Any]) -> Game:
         black_player=game_data.get('black_player'),
         result=game_data.get('result'),
         game_date=game_data.get('game_date'),
+        eco=game_data.get('eco'),
+        opening_name=game_data.get('opening_name'),
         analysis_status='queued'
     )
 
@@ -124,6 +199,7 @@ async def get_all_games(
     date_from: Optional[str] = None,
     date_to: Optional[str] = None,
     status: Optional[str] = None,
+    opening: Optional[str] = None,
     sort_by: str = 'date',
     sort_order: str = 'desc'
 ) -> List[Game]:
@@ -137,7 +213,8 @@ async def get_all_games(
         date_from: Filter games from this date (format: YYYY.MM.DD)
         date_to: Filter games to this date (format: YYYY.MM.DD)
         status: Filter by analysis status
-        sort_by: Field to sort by (date/result/status)
+        opening: Filter by ECO code prefix (e.g. 'B2') or part of the opening name
+        sort_by: Field to sort by (date/result/status/opening)
         sort_order: Sort order (asc/desc)
 
     Returns:
@@ -152,6 +229,8 @@ async def get_all_games(
         query = query.where(Game.game_date <= date_to)
     if status:
         query = query.where(Game.analysis_status == status)
+    if opening:
+        query = query.where(_opening_filter(opening))
 
     # Apply sorting
     sort_column = Game.game_date  # Default
@@ -159,6 +238,8 @@ async def get_all_games(
         sort_column = Game.result
     elif sort_by == 'status':
         sort_column = Game.analysis_status
+    elif sort_by == 'opening':
+        sort_column = Game.eco
     elif sort_by == 'date':
         sort_column = Game.game_date
 
@@ -178,7 +259,8 @@ async def get_games_count(
     db: AsyncSession,
     date_from: Optional[str] = None,
     date_to: Optional[str] = None,
-    status: Optional[str] = None
+    status: Optional[str] = None,
+    opening: Optional[str] = None
 ) -> int:
     """
     Get total count of games in the database with optional filters.
@@ -188,6 +270,7 @@ async def get_games_count(
         date_from: Filter games from this date (format: YYYY.MM.DD)
         date_to: Filter games to this date (format: YYYY.MM.DD)
         status: Filter by analysis status
+        opening: Filter by ECO code prefix (e.g. 'B2') or part of the opening name
 
     Returns:
         Total number of games matching filters
@@ -201,18 +284,83 @@ async def get_games_count(
         query = query.where(Game.game_date <= date_to)
     if status:
         query = query.where(Game.analysis_status == status)
+    if opening:
+        query = query.where(_opening_filter(opening))
 
     result = await db.execute(query)
     return result.scalar()
 
 
+async def get_game_ids(
+    db: AsyncSession,
+    date_from: Optional[str] = None,
+    date_to: Optional[str] = None,
+    status: Optional[str] = None,
+    opening: Optional[str] = None
+) -> List[int]:
+    """
+    Get the IDs of all games matching the filters, oldest first.
+
+    Args:
+        db: Database session
+        date_from: Filter games from this date (format: YYYY.MM.DD)
+        date_to: Filter games to this date (format: YYYY.MM.DD)
+        status: Filter by analysis status
+        opening: Filter by ECO code prefix (e.g. 'B2') or part of the opening name
+
+    Returns:
+        List of game IDs
+    """
+    query = select(Game.id)
+
+    # Apply filters
+    if date_from:
+        query = query.where(Game.game_date >= date_from)
+    if date_to:
+        query = query.where(Game.game_date <= date_to)
+    if status:
+        query = query.where(Game.analysis_status == status)
+    if opening:
+        query = query.where(_opening_filter(opening))
+
+    query = query.order_by(Game.game_date.asc(), Game.id.asc())
+
+    result = await db.execute(query)
+    return result.scalars().all()
+
+
+async def get_games_played_by(db: AsyncSession, game_ids: List[int], player: str) -> List[int]:
+    """
+    Keep the games in which a player had White or Black.
+
+    Args:
+        db: Database session
+        game_ids: IDs of the games to check
+        player: Username, compared case-insensitively
+
+    Returns:
+        IDs of the player's games, in the order given
+    """
+    if not game_ids:
+        return []
+
+    result = await db.execute(
+        select(Game.id).where(
+            Game.id.in_(game_ids),
+            or_(func.lower(Game.white_player) == player.lower(), func.lower(Game.black_player) == player.lower())
+        )
+    )
+    played = set(result.scalars().all())
+    return [game_id for game_id in game_ids if game_id in played]
+
+
 async def get_games_by_analysis_status(db: AsyncSession, status: str) -> List[Game]:
     """
     Get all games with a specific analysis status.
 
     Args:
         db: Database session
-        status: Analysis status to filter by (e.g., 'queued', 'analyzing', 'completed')
+        status: Analysis status to filter by (one of ANALYSIS_STATUSES)
 
     Returns:
         List of Game objects
@@ -223,33 +371,200 @@ async def get_games_by_analysis_status(db: AsyncSession, status: str) -> List[Ga
     return result.scalars().all()
 
 
+async def update_analysis_status_many(db: AsyncSession, games: List[Game], status: str) -> int:
+    """
+    Move several games to a new analysis state in one transaction.
+
+    Args:
+        db: Database session
+        games: Games to update
+        status: New analysis status (one of ANALYSIS_STATUSES)
+
+    Returns:
+        Number of games updated
+
+    Raises:
+        InvalidStatusTransition: If the transition is not allowed for one of the games
+    """
+    for game in games:
+        apply_analysis_status(game, status)
+
+    await db.commit()
+    return len(games)
+
+
 async def update_game_analysis_status(
     db: AsyncSession,
     game_id: int,
     status: str,
-    analysis_data: Optional[str] = None
+    analysis_data: Optional[str] = None,
+    error: Optional[str] = None,
+    force: bool = False
 ) -> Optional[Game]:
     """
-    Update the analysis status and data for a game.
+    Move a game to a new analysis state.
 
     Args:
         db: Database session
         game_id: Database ID of the game
-        status: New analysis status ('queued', 'analyzing', 'completed')
+        status: New analysis status (one of ANALYSIS_STATUSES)
         analysis_data: Optional analysis data (JSON string)
+        error: Error message for 'failed', or of the failed attempt when re-queued
+        force: Skip the transition check (for repairs at startup)
 
     Returns:
         Updated Game object if found, None otherwise
+
+    Raises:
+        InvalidStatusTransition: If the transition is not allowed
     """
     game = await get_game_by_id(db, game_id)
 
     if not game:
         return None
 
-    game.analysis_status = status
+    apply_analysis_status(game, status, error, force)
     if analysis_data is not None:
         game.analysis_data = analysis_data
 
     await db.commit()
     await db.refresh(game)
     return game
+
+
+async def get_analysis_status_counts(db: AsyncSession) -> Dict[str, int]:
+    """
+    Count games in each analysis state.
+
+    Args:
+        db: Database session
+
+    Returns:
+        Dictionary mapping every analysis status to its game count
+    """
+    result = await db.execute(
+        select(Game.analysis_status, func.count(Game.id)).group_by(Game.analysis_status)
+    )
+    counts = {status: 0 for status in ANALYSIS_STATUSES}
+    for status, count in result.all():
+        counts[status or 'queued'] = counts.get(status or 'queued', 0) + count
+    return counts
+
+
+async def update_game_accuracy(
+    db: AsyncSession,
+    game_id: int,
+    white_accuracy: Optional[float],
+    black_accuracy: Optional[float]
+) -> Optional[Game]:
+    """
+    Store the per-player accuracy computed by the game analysis.
+
+    Args:
+        db: Database session
+        game_id: Database ID of the game
+        white_accuracy: White's accuracy in percent
+        black_accuracy: Black's accuracy in percent
+
+    Returns:
+        Updated Game object if found, None otherwise
+    """
+    game = await get_game_by_id(db, game_id)
+
+    if not game:
+        return None
+
+    game.white_accuracy = white_accuracy
+    game.black_accuracy = black_accuracy
+
+    await db.commit()
+    await db.refresh(game)
+    return game
+
+
+async def get_games_missing_accuracy(db: AsyncSession) -> List[Game]:
+    """
+    Get completed games that have no accuracy stored yet.
+
+    Args:
+        db: Database session
+
+    Returns:
+        List of Game objects
+    """
+    result = await db.execute(
+        select(Game).where(
+            Game.analysis_status == 'completed',
+            Game.white_accuracy.is_(None)
+        )
+    )
+    return result.scalars().all()
+
+
+async def update_game_opening(
+    db: AsyncSession,
+    game_id: int,
+    eco: str,
+    opening_name: str
+) -> Optional[Game]:
+    """
+    Store the ECO code and opening name of a game.
+
+    Args:
+        db: Database session
+        game_id: Database ID of the game
+        eco: ECO code (empty string if unknown)
+        opening_name: Opening name (empty string if unknown)
+
+    Returns:
+        Updated Game object if found, None otherwise
+    """
+    game = await get_game_by_id(db, game_id)
+
+    if not game:
+        return None
+
+    game.eco = eco
+    game.opening_name = opening_name
+
+    await db.commit()
+    await db.refresh(game)
+    return game
+
+
+async def get_games_missing_opening(db: AsyncSession) -> List[Game]:
+    """
+    Get games whose opening has not been classified yet.
+
+    Args:
+        db: Database session
+
+    Returns:
+        List of Game objects
+    """
+    result = await db.execute(
+        select(Game).where(Game.eco.is_(None))
+    )
+    return result.scalars().all()
+
+
+async def get_opening_counts(db: AsyncSession) -> List[Dict[str, Any]]:
+    """
+    Count games per opening, most played first.
+
+    Args:
+        db: Database session
+
+    Returns:
+        List of dictionaries with eco, opening_name and count
+    """
+    result = await db.execute(
+        select(Game.eco, Game.opening_name, func.count(Game.id).label("count"))
+        .where(Game.eco.isnot(None), Game.eco != '')
+        .group_by(Game.eco, Game.opening_name)
+        .order_by(func.count(Game.id).desc(), Game.eco.asc())
+    )
+    return [
+        {"eco": eco, "opening_name": opening_name, "count": count}
+        for eco, opening_name, count in result.all()
+    ]
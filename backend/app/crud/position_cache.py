@@ -0,0 +1,132 @@
+from sqlalchemy.ext.asyncio import AsyncSession
+from sqlalchemy.future import select
+from sqlalchemy import func, delete
+from sqlalchemy.dialects.sqlite import insert
+from ..db.models import PositionCache
+from typing import List, Dict, Any
+import json
+
+
+STORE_CHUNK_SIZE = 100
+
+
+async def get_cached_positions(
+    db: AsyncSession,
+    fens: List[str],
+    engine: str,
+    depth: int,
+    time_ms: int,
+    multipv: int
+) -> Dict[str, Dict[str, Any]]:
+    """
+    Get cached analyses by the same engine searched at least as deep,
+    as long and as wide as requested.
+
+    Args:
+        db: Database session
+        fens: Normalized FENs to look up
+        engine: Engine identity from engine_cache_key
+        depth: Minimum search depth
+        time_ms: Minimum time per move in milliseconds
+        multipv: Minimum number of candidate lines
+
+    Returns:
+        Dictionary mapping normalized FEN to the best cached analysis
+    """
+    if not fens:
+        return {}
+
+    result = await db.execute(
+        select(PositionCache)
+        .where(
+            PositionCache.fen.in_(set(fens)),
+            PositionCache.engine == engine,
+            PositionCache.depth >= depth,
+            PositionCache.time_ms >= time_ms,
+            PositionCache.multipv >= multipv
+        )
+        .order_by(PositionCache.depth.asc(), PositionCache.time_ms.asc())
+    )
+
+    # Later rows come from deeper searches and overwrite shallower ones
+    cached = {}
+    for entry in result.scalars().all():
+        cached[entry.fen] = json.loads(entry.analysis)
+    return cached
+
+
+async def store_positions(
+    db: AsyncSession,
+    analyses: Dict[str, Dict[str, Any]],
+    engine: str,
+    depth: int,
+    time_ms: int,
+    multipv: int
+) -> int:
+    """
+    Store position analyses, ignoring positions already cached with the same settings.
+
+    Args:
+        db: Database session
+        analyses: Dictionary mapping normalized FEN to its analysis
+        engine: Identity of the engine that made them, from engine_cache_key
+        depth: Search depth the analyses were made with
+        time_ms: Time per move in milliseconds
+        multipv: Number of candidate lines
+
+    Returns:
+        Number of positions submitted
+    """
+    if not analyses:
+        return 0
+
+    rows = [
+        {
+            "fen": fen,
+            "engine": engine,
+            "depth": depth,
+            "time_ms": time_ms,
+            "multipv": multipv,
+            "analysis": json.dumps(analysis)
+        }
+        for fen, analysis in analyses.items()
+    ]
+
+    # Insert in chunks to stay below SQLite's bound parameter limit
+    for start in range(0, len(rows), STORE_CHUNK_SIZE):
+        await db.execute(
+            insert(PositionCache)
+            .values(rows[start:start + STORE_CHUNK_SIZE])
+            .on_conflict_do_nothing()
+        )
+    await db.commit()
+    return len(analyses)
+
+
+async def count_positions(db: AsyncSession) -> int:
+    """
+    Count cached position analyses.
+
+    Args:
+        db: Database session
+
+    Returns:
+        Number of cache entries
+    """
+    result = await db.execute(select(func.count(PositionCache.id)))
+    return result.scalar() or 0
+
+
+async def purge_positions(db: AsyncSession) -> int:
+    """
+    Delete all cached position analyses.
+
+    Args:
+        db: Database session
+
+    Returns:
+        Number of deleted entries
+    """
+    result = await db.execute(delete(PositionCache))
+    await db.commit()
+    return result.rowcount
@@ -0,0 +1,35 @@
+from sqlalchemy import text
+from sqlalchemy.ext.asyncio import AsyncConnection
+
+
+# Columns added to existing tables after their first release.
+# create_all only creates missing tables, so these are added with ALTER TABLE.
+ADDED_COLUMNS = {
+    "games": [
+        ("white_accuracy", "FLOAT"),
+        ("black_accuracy", "FLOAT"),
+        ("eco", "VARCHAR"),
+        ("opening_name", "VARCHAR"),
+        ("analysis_error", "TEXT"),
+        ("analysis_attempts", "INTEGER DEFAULT 0"),
+        ("analysis_queued_at", "TIMESTAMP"),
+        ("analysis_started_at", "TIMESTAMP"),
+        ("analysis_finished_at", "TIMESTAMP"),
+    ],
+}
+
+
+async def run_migrations(conn: AsyncConnection):
+    """
+    Add columns introduced after a table was first created.
+
+    Args:
+        conn: Open database connection (inside a transaction)
+    """
+    for table, columns in ADDED_COLUMNS.items():
+        result = await conn.execute(text(f"PRAGMA table_info({table})"))
+        existing = {row[1] for row in result.fetchall()}
+
+        for name, column_type in columns:
+            if name not in existing:
+                await conn.execute(text(f"ALTER TABLE {table} ADD COLUMN {name} {column_type}"))
@@ -1,4 +1,4 @@
-from sqlalchemy import Column, Integer, String, Text, TIMESTAMP, func
+from sqlalchemy import Column, Integer, String, Text, Float, Boolean, TIMESTAMP, ForeignKey, UniqueConstraint, func
 from .database import Base
 
 class Game(Base):
@@ -12,12 +12,127 @@ class Game(Base):
     result = Column(String)
     game_date = Column(String)
     import_date = Column(TIMESTAMP, server_default=func.now())
-    analysis_status = Column(String, default='queued')
+    analysis_status = Column(String, default='queued')  # See crud.games.ANALYSIS_STATUS_TRANSITIONS
     analysis_data = Column(Text)
+    analysis_error = Column(Text)  # Error of the last failed attempt
+    analysis_attempts = Column(Integer, default=0)  # Attempts since the game was last queued
+    analysis_queued_at = Column(TIMESTAMP)
+    analysis_started_at = Column(TIMESTAMP)
+    analysis_finished_at = Column(TIMESTAMP)
+    white_accuracy = Column(Float)
+    black_accuracy = Column(Float)
+    eco = Column(String, index=True)  # Empty string when no known opening was reached
+    opening_name = Column(String)
 
 class Setting(Base):
     __tablename__ = "settings"
 
     key = Column(String, primary_key=True, unique=True, nullable=False)
     value = Column(String)
-    
+
+
+class AnalysisJob(Base):
+    __tablename__ = "analysis_jobs"
+
+    id = Column(Integer, primary_key=True, index=True)
+    game_id = Column(Integer, ForeignKey("games.id", ondelete="CASCADE"), nullable=False, index=True)
+    status = Column(String, default='queued', index=True)
+    priority = Column(Integer, default=0)
+    attempts = Column(Integer, default=0)
+    error = Column(Text)
+    options = Column(Text)  # JSON-encoded setting overrides; set for re-analysis jobs
+    created_at = Column(TIMESTAMP, server_default=func.now())
+    started_at = Column(TIMESTAMP)
+    finished_at = Column(TIMESTAMP)
+
+
+class AnalysisPreset(Base):
+    __tablename__ = "analysis_presets"
+
+    id = Column(Integer, primary_key=True, index=True)
+    name = Column(String, unique=True, nullable=False)
+    depth = Column(Integer, nullable=False)
+    time_ms = Column(Integer, nullable=False)
+    multipv = Column(Integer, nullable=False)
+    created_at = Column(TIMESTAMP, server_default=func.now())
+
+
+class EngineProfile(Base):
+    __tablename__ = "engines"
+
+    id = Column(Integer, primary_key=True, index=True)
+    name = Column(String, unique=True, nullable=False)
+    label = Column(String)  # Display name, e.g. "Leela (CPU)"
+    path = Column(String, nullable=False)
+    uci_options = Column(Text)  # JSON-encoded UCI options set after Threads/Hash/MultiPV
+    id_name = Column(String)  # "id name" the engine reported at registration
+    id_author = Column(String)
+    created_at = Column(TIMESTAMP, server_default=func.now())
+
+
+class PositionCache(Base):
+    __tablename__ = "position_cache"
+    __table_args__ = (UniqueConstraint("fen", "engine", "depth", "time_ms", "multipv"),)
+
+    id = Column(Integer, primary_key=True, index=True)
+    fen = Column(String, nullable=False, index=True)  # Normalized, without move counters
+    engine = Column(String, nullable=False, default="")  # Hash of the engine path and UCI options
+    depth = Column(Integer, nullable=False)
+    time_ms = Column(Integer, nullable=False)
+    multipv = Column(Integer, nullable=False)
+    analysis = Column(Text, nullable=False)  # JSON-encoded position analysis
+    created_at = Column(TIMESTAMP, server_default=func.now())
+
+
+class Analysis(Base):
+    __tablename__ = "analyses"
+    __table_args__ = (UniqueConstraint("game_id", "version"),)
+
+    id = Column(Integer, primary_key=True, index=True)
+    game_id = Column(Integer, ForeignKey("games.id", ondelete="CASCADE"), nullable=False, index=True)
+    version = Column(Integer, nullable=False, default=1)  # Counts up per game; the highest is current
+    created_at = Column(TIMESTAMP, server_default=func.now())
+    engine_name = Column(String)  # As reported by the engine, e.g. "Stockfish 16.1"
+    engine_path = Column(String)
+    preset = Column(String)  # Name of the analysis preset used, if any
+    engine_profile = Column(String)  # Name of the engine profile used, None for the stockfish_path setting
+    scope = Column(String)  # Plies analysed: all, mine, range or critical
+    settings_fingerprint = Column(String)  # Hash of engine path and search settings
+    depth = Column(Integer)
+    time_ms = Column(Integer)
+    threads = Column(Integer)
+    hash_mb = Column(Integer)
+    multipv = Column(Integer)
+    total_moves = Column(Integer)
+    final_fen = Column(String)
+    white_accuracy = Column(Float)
+    black_accuracy = Column(Float)
+    game_info = Column(Text)  # JSON-encoded players, result and date from the PGN
+    settings = Column(Text)  # JSON-encoded analysis settings
+    final_analysis = Column(Text)  # JSON-encoded analysis of the final position
+    classification_summary = Column(Text)  # JSON-encoded counts per classification
+    player_stats = Column(Text)  # JSON-encoded accuracy/ACPL per player
+
+
+class AnalysedPosition(Base):
+    __tablename__ = "analysed_positions"
+    __table_args__ = (UniqueConstraint("analysis_id", "ply"),)
+
+    id = Column(Integer, primary_key=True, index=True)
+    analysis_id = Column(Integer, ForeignKey("analyses.id", ondelete="CASCADE"), nullable=False, index=True)
+    ply = Column(Integer, nullable=False)  # 0-based index of the move in the game
+    move_number = Column(Integer)
+    move_san = Column(String)
+    move_uci = Column(String)
+    fen = Column(String, nullable=False, index=True)  # Position before the move
+    fen_after = Column(String)
+    score_type = Column(String)  # cp, mate, book or error
+    score_value = Column(Integer)  # White's perspective
+    best_move = Column(String)
+    pv = Column(Text)  # Space-separated UCI moves of the best line
+    depth = Column(Integer)
+    classification = Column(String, index=True)
+    cp_loss = Column(Integer)
+    accuracy = Column(Float)
+    book = Column(Boolean, default=False)
+    data = Column(Text, nullable=False)  # JSON-encoded move entry with all lines and annotations
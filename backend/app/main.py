@@ -1,8 +1,9 @@
 from fastapi import FastAPI
 from fastapi.middleware.cors import CORSMiddleware
 from contextlib import asynccontextmanager
-from .db.database import engine, Base
+from .db.database import engine, Base, async_session
 from .db.models import Game, Setting
+from .db.migrations import run_migrations
 from sqlalchemy.sql import text
 import os
 import stat
@@ -12,6 +13,14 @@ from .api import sync as sync_api
 from .api import games as games_api
 from .api import system_resources as system_resources_api
 from .api import database as database_api
+from .api import analysis as analysis_api
+from .api import engines as engines_api
+from .crud import settings as crud_settings
+from .services.stockfish_service import backfill_game_accuracy, reconcile_analysis_status, import_analysis_files
+from .services.analysis_queue import worker_pool
+from .services.engine_pool import engine_pool
+from .services.openings import backfill_game_openings
+from .services.analysis_staleness import mark_stale_analyses
 
 @asynccontextmanager
 async def lifespan(app: FastAPI):
@@ -34,21 +43,94 @@ async def lifespan(app: FastAPI):
     # Initialize database tables and default settings
     async with engine.begin() as conn:
         await conn.run_sync(Base.metadata.create_all)
+        await run_migrations(conn)
         await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('chess_com_username', NULL)"))
         await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('stockfish_path', '/app/stockfish/stockfish_binary')"))
         await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('stockfish_threads', '1')"))
         await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('stockfish_hash_mb', '128')"))
         await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('analysis_depth', '15')"))
         await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('analysis_time_ms', '1000')"))
+        await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('analysis_multipv', '3')"))
+        await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('analysis_position_retries', '2')"))
+        await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('analysis_search_timeout_s', '300')"))
+        await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('position_cache_enabled', 'true')"))
+        await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('book_enabled', 'true')"))
+        await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('book_max_ply', '20')"))
+        await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('book_polyglot_path', '')"))
+        await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('book_search_depth', '0')"))
+        await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('syzygy_path', '')"))
+        await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('syzygy_probe_limit', '7')"))
+        await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('analysis_limit_mode', 'depth_time')"))
+        await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('analysis_nodes', '1000000')"))
+        await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('analysis_mate_moves', '5')"))
+        await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('deterministic_mode', 'false')"))
+        await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('default_engine', '')"))
+        await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('critical_pass_depth', '10')"))
+        await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('critical_swing_cp', '100')"))
+        await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('adaptive_analysis_enabled', 'false')"))
+        await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('adaptive_time_budget_s', '120')"))
+        await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('analysis_workers', '1')"))
+        await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('engine_pool_size', '1')"))
+        await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('classification_best_cp', '10')"))
+        await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('classification_inaccuracy_cp', '50')"))
+        await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('classification_mistake_cp', '100')"))
+        await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('classification_blunder_cp', '300')"))
         await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('auto_sync_enabled', 'false')"))
         await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('theme', 'default')"))
 
+        # Presets per time class; empty uses the analysis settings above
+        await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('preset_bullet', 'quick')"))
+        await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('preset_blitz', '')"))
+        await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('preset_rapid', '')"))
+        await conn.execute(text("INSERT OR IGNORE INTO settings (key, value) VALUES ('preset_classical', 'deep')"))
+
+        # Default analysis presets, only on first start so deleted presets stay deleted
+        result = await conn.execute(text("SELECT COUNT(*) FROM analysis_presets"))
+        if result.scalar() == 0:
+            await conn.execute(text("INSERT INTO analysis_presets (name, depth, time_ms, multipv) VALUES ('quick', 10, 200, 1)"))
+            await conn.execute(text("INSERT INTO analysis_presets (name, depth, time_ms, multipv) VALUES ('standard', 15, 1000, 3)"))
+            await conn.execute(text("INSERT INTO analysis_presets (name, depth, time_ms, multipv) VALUES ('deep', 22, 3000, 3)"))
+
     # Ensure newly created database file has proper permissions
     if os.path.exists(db_path):
         os.chmod(db_path, 0o666)
 
+    async with async_session() as db:
+        # Analyses used to be stored as JSON files next to the database
+        imported = await import_analysis_files(db)
+        if imported:
+            print(f"Imported {imported} analysis files into the database")
+
+        # Status changes only happen in the analysis queue - repair games it didn't track
+        reconciled = await reconcile_analysis_status(db)
+        if reconciled:
+            print(f"Marked {reconciled} games with existing analysis files as completed")
+
+        # Settings may have changed while the backend was down
+        staleness = await mark_stale_analyses(db)
+        if staleness["stale"]:
+            print(f"Marked {staleness['stale']} games analysed with weaker settings as stale")
+
+        # Compute accuracy for games analysed before it was stored
+        backfilled = await backfill_game_accuracy(db)
+        if backfilled:
+            print(f"Backfilled accuracy for {backfilled} analysed games")
+
+        # Classify openings of games imported before openings were stored
+        classified = await backfill_game_openings(db)
+        if classified:
+            print(f"Classified openings for {classified} games")
+
+        # Start background analysis workers and size the engine pool
+        settings = await crud_settings.get_all_settings(db)
+        await engine_pool.resize(int(settings.get("engine_pool_size") or 1))
+        await worker_pool.start(int(settings.get("analysis_workers") or 1))
+
     yield
 
+    await worker_pool.stop()
+    await engine_pool.shutdown()
+
 
 app = FastAPI(lifespan=lifespan)
 
@@ -66,6 +148,8 @@ app.include_router(sync_api.router)
 app.include_router(games_api.router)
 app.include_router(system_resources_api.router)
 app.include_router(database_api.router)
+app.include_router(analysis_api.router)
+app.include_router(engines_api.router)
 
 @app.get("/")
 def read_root():
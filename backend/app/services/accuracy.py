@@ -0,0 +1,194 @@
+import math
+from typing import Dict, List, Optional, Any
+
+from .move_classification import score_to_cp, wdl_expected_score
+from .tablebase import tablebase_outcome
+
+
+def win_probability(cp: int) -> float:
+    """
+    Convert a centipawn evaluation into White's winning chances.
+
+    Uses the logistic model fitted by Lichess on rated games, so +1 pawn is
+    roughly 59% and the curve flattens out in clearly won positions.
+
+    Args:
+        cp: Evaluation in centipawns from White's perspective
+
+    Returns:
+        Win probability for White in percent (0-100)
+    """
+    cp = max(-10000, min(10000, cp))
+    winning_chances = 2 / (1 + math.exp(-0.00368208 * cp)) - 1
+    return 50 + 50 * winning_chances
+
+
+def analysis_win_probability(analysis: Optional[Dict[str, Any]]) -> Optional[float]:
+    """
+    Get White's win probability for an analysed position.
+
+    Tablebase-resolved positions use the exact result (100, 50 or 0).
+    Otherwise the engine's WDL statistics are preferred over the centipawn
+    model, so drawish positions with a small edge stay close to 50%.
+
+    Args:
+        analysis: Position analysis as produced by StockfishAnalyzer.analyze_position
+
+    Returns:
+        Win probability for White in percent, or None if the position has no score
+    """
+    outcome = tablebase_outcome(analysis)
+    if outcome is not None:
+        return 50.0 + 50.0 * outcome
+
+    wdl = analysis.get("wdl") if analysis else None
+    if wdl:
+        return wdl_expected_score(wdl)
+
+    cp = score_to_cp(analysis)
+    if cp is None:
+        return None
+    return win_probability(cp)
+
+
+def move_accuracy(win_before: float, win_after: float) -> float:
+    """
+    Compute the accuracy of a single move from the mover's win probabilities.
+
+    Args:
+        win_before: Mover's win probability before the move (percent)
+        win_after: Mover's win probability after the move (percent)
+
+    Returns:
+        Move accuracy in percent (0-100)
+    """
+    if win_after >= win_before:
+        return 100.0
+    accuracy = 103.1668100711649 * math.exp(-0.04354415386753951 * (win_before - win_after)) - 3.166924740191411
+    return max(0.0, min(100.0, accuracy))
+
+
+def _harmonic_mean(values: List[float]) -> float:
+    # Zero accuracies would make the harmonic mean collapse to zero
+    return len(values) / sum(1 / max(value, 1.0) for value in values)
+
+
+def _volatility_weights(win_probabilities: List[float], move_count: int) -> List[float]:
+    """
+    Weight each move by how volatile the game was around it.
+
+    Moves played in sharp positions count more than moves played while
+    the evaluation was quiet.
+    """
+    window_size = max(2, min(8, move_count // 10))
+    weights = []
+
+    for index in range(move_count):
+        start = max(0, min(index, len(win_probabilities) - window_size))
+        window = win_probabilities[start:start + window_size]
+        mean = sum(window) / len(window)
+        deviation = math.sqrt(sum((value - mean) ** 2 for value in window) / len(window))
+        weights.append(max(0.5, min(12.0, deviation)))
+
+    return weights
+
+
+def annotate_win_probabilities(
+    moves: List[Dict[str, Any]],
+    final_analysis: Optional[Dict[str, Any]]
+) -> None:
+    """
+    Add a "win_probability" (White's perspective) to every analysed position
+    and an "accuracy" to every move whose evaluation before and after is known.
+    Opening book moves get no accuracy, so they don't count towards the game accuracy.
+    The win probability after a move comes from the played move's own score
+    when available, unless the position is tablebase-resolved.
+
+    Args:
+        moves: Move entries from StockfishAnalyzer.analyze_game
+        final_analysis: Analysis of the final position of the game
+    """
+    analyses = [move.get("analysis") for move in moves] + [final_analysis]
+
+    for analysis in analyses:
+        if analysis is not None:
+            analysis["win_probability"] = analysis_win_probability(analysis)
+
+    for index, move in enumerate(moves):
+        win_before = analyses[index].get("win_probability") if analyses[index] else None
+        win_after = analyses[index + 1].get("win_probability") if analyses[index + 1] else None
+
+        played = move.get("played_move_analysis")
+        if played and tablebase_outcome(analyses[index]) is None:
+            win_after = analysis_win_probability(played)
+
+        if move.get("book") or win_before is None or win_after is None:
+            move["accuracy"] = None
+            continue
+
+        if move["fen_before"].split()[1] == "b":
+            win_before, win_after = 100 - win_before, 100 - win_after
+
+        move["accuracy"] = round(move_accuracy(win_before, win_after), 1)
+
+
+def compute_player_stats(moves: List[Dict[str, Any]]) -> Dict[str, Dict[str, Any]]:
+    """
+    Compute accuracy and average centipawn loss for both players.
+
+    Game accuracy is the mean of a volatility-weighted mean and the harmonic
+    mean of per-move accuracies, which punishes single blunders harder than
+    a plain average would.
+
+    Args:
+        moves: Move entries annotated by annotate_win_probabilities and classify_moves
+
+    Returns:
+        Dictionary with "white" and "black" stats (accuracy, acpl, analysed_moves)
+    """
+    # One value per ply, so the weights line up with the move indexes; book and
+    # skipped plies carry the previous value forward (the first known one at the start)
+    scored = [
+        move["analysis"]["win_probability"]
+        if move.get("analysis") and move["analysis"].get("win_probability") is not None else None
+        for move in moves
+    ]
+    known = [value for value in scored if value is not None]
+    if known:
+        win_probabilities = []
+        previous = known[0]
+        for value in scored:
+            previous = value if value is not None else previous
+            win_probabilities.append(previous)
+        weights = _volatility_weights(win_probabilities, len(moves))
+    else:
+        weights = [1.0] * len(moves)
+
+    stats = {}
+    for side, turn in (("white", "w"), ("black", "b")):
+        accuracies = []
+        accuracy_weights = []
+        cp_losses = []
+
+        for index, move in enumerate(moves):
+            if move["fen_before"].split()[1] != turn:
+                continue
+            if move.get("accuracy") is not None:
+                accuracies.append(move["accuracy"])
+                accuracy_weights.append(weights[index])
+            if move.get("cp_loss") is not None:
+                cp_losses.append(move["cp_loss"])
+
+        if accuracies:
+            weighted_mean = sum(a * w for a, w in zip(accuracies, accuracy_weights)) / sum(accuracy_weights)
+            accuracy = round((weighted_mean + _harmonic_mean(accuracies)) / 2, 1)
+        else:
+            accuracy = None
+
+        stats[side] = {
+            "accuracy": accuracy,
+            "acpl": round(sum(cp_losses) / len(cp_losses)) if cp_losses else None,
+            "analysed_moves": len(accuracies)
+        }
+
+    return stats
@@ -0,0 +1,58 @@
+from typing import Dict, Any, Optional
+
+
+def _move_summary(move: Dict[str, Any]) -> Dict[str, Any]:
+    analysis = move.get("analysis") or {}
+    return {
+        "classification": move.get("classification"),
+        "cp_loss": move.get("cp_loss"),
+        "accuracy": move.get("accuracy"),
+        "score": analysis.get("score"),
+        "score_type": analysis.get("score_type"),
+        "score_value": analysis.get("score_value"),
+        "best_move": analysis.get("best_move"),
+        "depth": analysis.get("depth")
+    }
+
+
+def diff_analyses(base: Dict[str, Any], other: Dict[str, Any]) -> Dict[str, Any]:
+    """
+    Compare two analyses of the same game move by move.
+
+    Args:
+        base: Older analysis dictionary (as returned by get_game_analysis)
+        other: Newer analysis dictionary
+
+    Returns:
+        Dictionary with both versions, player stats, a per-move comparison
+        and the number of moves whose classification changed
+    """
+    moves = []
+    changed = 0
+
+    for ply, (base_move, other_move) in enumerate(zip(base.get("moves", []), other.get("moves", []))):
+        base_summary = _move_summary(base_move)
+        other_summary = _move_summary(other_move)
+        classification_changed = base_summary["classification"] != other_summary["classification"]
+        changed += classification_changed
+
+        moves.append({
+            "ply": ply,
+            "move_number": base_move.get("move_number"),
+            "move": base_move.get("move"),
+            "base": base_summary,
+            "other": other_summary,
+            "classification_changed": classification_changed,
+            "best_move_changed": base_summary["best_move"] != other_summary["best_move"]
+        })
+
+    return {
+        "base": base.get("version"),
+        "other": other.get("version"),
+        "player_stats": {
+            "base": base.get("player_stats"),
+            "other": other.get("player_stats")
+        },
+        "moves": moves,
+        "changed_classifications": changed
+    }
@@ -0,0 +1,92 @@
+import asyncio
+from typing import Dict, List, Optional, Any
+
+
+class AnalysisEventBroker:
+    """
+    In-process publish/subscribe hub for analysis progress events.
+    Each subscriber gets its own queue, optionally limited to one game.
+    """
+
+    def __init__(self, max_queue_size: int = 1000):
+        """
+        Initialize the event broker.
+
+        Args:
+            max_queue_size: Events buffered per subscriber before new ones are dropped
+        """
+        self.max_queue_size = max_queue_size
+        self.subscribers: List[tuple[Optional[int], asyncio.Queue]] = []
+        self.progress: Dict[int, Dict[str, Any]] = {}
+        self.analysed_moves: Dict[int, List[Dict[str, Any]]] = {}
+
+    def subscribe(self, game_id: Optional[int] = None) -> asyncio.Queue:
+        """
+        Register a new subscriber.
+
+        Args:
+            game_id: Only receive events for this game (None for all games)
+
+        Returns:
+            Queue that receives event dictionaries
+        """
+        queue = asyncio.Queue(maxsize=self.max_queue_size)
+        self.subscribers.append((game_id, queue))
+        return queue
+
+    def unsubscribe(self, queue: asyncio.Queue):
+        """Remove a subscriber registered with subscribe()."""
+        self.subscribers = [(game_id, q) for game_id, q in self.subscribers if q is not queue]
+
+    def snapshot(self, game_id: Optional[int] = None) -> List[Dict[str, Any]]:
+        """
+        Get the latest progress of analyses that are currently running.
+
+        Each event carries "moves" with every move analysed so far, so a
+        client connecting mid-analysis can fill in the earlier evaluations.
+
+        Args:
+            game_id: Only return progress for this game (None for all games)
+
+        Returns:
+            List of the most recent progress events
+        """
+        return [
+            {**event, "moves": self.analysed_moves.get(event_game_id, [])}
+            for event_game_id, event in self.progress.items()
+            if game_id is None or event_game_id == game_id
+        ]
+
+    def publish(self, event: Dict[str, Any]):
+        """
+        Send an event to all matching subscribers.
+
+        Progress events are also remembered so that late subscribers can
+        catch up; any other event for the game clears that state.
+
+        Args:
+            event: Event dictionary with at least "type" and "game_id"
+        """
+        game_id = event.get("game_id")
+
+        if event["type"] == "started":
+            self.progress[game_id] = event
+            self.analysed_moves[game_id] = []
+        elif event["type"] == "progress":
+            self.progress[game_id] = event
+            self.analysed_moves.setdefault(game_id, []).append(event["move"])
+        else:
+            self.progress.pop(game_id, None)
+            self.analysed_moves.pop(game_id, None)
+
+        for subscribed_game_id, queue in self.subscribers:
+            if subscribed_game_id is not None and subscribed_game_id != game_id:
+                continue
+            try:
+                queue.put_nowait(event)
+            except asyncio.QueueFull:
+                pass  # Slow client - drop the event rather than block analysis
+
+
+# Global event broker shared by the analysis workers and the streaming endpoints
+event_broker = AnalysisEventBroker()
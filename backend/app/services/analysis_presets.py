@@ -0,0 +1,124 @@
+import io
+from typing import Dict, Any, Optional, Tuple
+
+import chess.pgn
+from sqlalchemy.ext.asyncio import AsyncSession
+
+from ..db.models import AnalysisPreset
+from ..crud import analysis_presets as crud_presets
+
+
+# Time classes with a configurable default preset (settings preset_<class>)
+TIME_CLASSES = ("bullet", "blitz", "rapid", "classical")
+
+
+def time_class_from_time_control(time_control: Optional[str]) -> Optional[str]:
+    """
+    Derive the time class from a PGN TimeControl header.
+
+    Uses Chess.com's rule: the base time plus 40 increments decides the class.
+    Daily games ("1/86400") count as classical.
+
+    Args:
+        time_control: Header value, e.g. "180+2", "600" or "1/86400"
+
+    Returns:
+        One of TIME_CLASSES, or None if the header is missing or unknown
+    """
+    if not time_control or time_control in ("-", "?"):
+        return None
+
+    if "/" in time_control:
+        return "classical"
+
+    try:
+        base, _, increment = time_control.partition("+")
+        estimated_seconds = int(base) + 40 * int(increment or 0)
+    except ValueError:
+        return None
+
+    if estimated_seconds < 180:
+        return "bullet"
+    if estimated_seconds < 600:
+        return "blitz"
+    if estimated_seconds < 3600:
+        return "rapid"
+    return "classical"
+
+
+def game_time_class(pgn_text: str) -> Optional[str]:
+    """
+    Get the time class of a game from its PGN headers.
+
+    Args:
+        pgn_text: Game in PGN format
+
+    Returns:
+        One of TIME_CLASSES, or None if it can't be determined
+    """
+    headers = chess.pgn.read_headers(io.StringIO(pgn_text))
+    return time_class_from_time_control(headers.get("TimeControl")) if headers else None
+
+
+def preset_overrides(preset: AnalysisPreset) -> Dict[str, int]:
+    """
+    Get the setting overrides a preset stands for.
+
+    Args:
+        preset: Analysis preset
+
+    Returns:
+        Dictionary with depth, time_ms and multipv (keys of SETTING_OVERRIDES)
+    """
+    return {"depth": preset.depth, "time_ms": preset.time_ms, "multipv": preset.multipv}
+
+
+def default_preset_name(pgn_text: str, settings: Dict[str, Any]) -> Optional[str]:
+    """
+    Get the preset configured for a game's time class.
+
+    Args:
+        pgn_text: Game in PGN format
+        settings: Settings dictionary from get_stockfish_settings
+
+    Returns:
+        Preset name, or None if the analysis settings apply
+    """
+    return settings["time_class_presets"].get(game_time_class(pgn_text)) or None
+
+
+async def resolve_analysis_options(
+    db: AsyncSession,
+    pgn_text: str,
+    options: Optional[Dict[str, Any]],
+    settings: Dict[str, Any]
+) -> Tuple[Dict[str, int], Optional[str]]:
+    """
+    Turn the options of an analysis job into setting overrides.
+
+    The requested preset, or else the default preset of the game's time class,
+    provides the base values; explicit options replace single values of it.
+
+    Args:
+        db: Database session
+        pgn_text: Game in PGN format
+        options: Job options (optional "preset" plus keys of SETTING_OVERRIDES)
+        settings: Settings dictionary from get_stockfish_settings
+
+    Returns:
+        Tuple of the overrides and the name of the applied preset (None if none)
+    """
+    options = dict(options or {})
+    preset_name = options.pop("preset", None) or default_preset_name(pgn_text, settings)
+
+    overrides = {}
+    if preset_name:
+        preset = await crud_presets.get_preset_by_name(db, preset_name)
+        if preset:
+            overrides = preset_overrides(preset)
+        else:
+            print(f"Analysis preset '{preset_name}' not found, using the analysis settings")
+            preset_name = None
+
+    overrides.update(options)
+    return overrides, preset_name
@@ -0,0 +1,328 @@
+import asyncio
+import json
+from typing import Dict, List, Optional
+
+from sqlalchemy.ext.asyncio import AsyncSession
+
+from ..db.database import async_session
+from ..crud import games as crud_games
+from ..crud import analysis_jobs as crud_jobs
+from .stockfish_service import analyze_game_async, has_game_analysis, AnalysisCancelled
+from .analysis_staleness import mark_stale_analyses
+from .analysis_events import event_broker
+
+
+# Attempts per job before it is marked as failed; checkpoints let
+# each retry continue where the previous attempt stopped
+MAX_JOB_ATTEMPTS = 3
+
+
+class AnalysisWorkerPool:
+    """
+    Pool of background workers that process the analysis job queue.
+    Jobs are stored in the database, so the queue survives restarts.
+    """
+
+    def __init__(self, poll_interval: float = 5.0):
+        """
+        Initialize the worker pool.
+
+        Args:
+            poll_interval: Seconds an idle worker waits before checking the queue again
+        """
+        self.poll_interval = poll_interval
+        self.worker_count = 0
+        self.workers: Dict[int, asyncio.Task] = {}  # worker slot -> task
+        self.cancel_events: Dict[int, asyncio.Event] = {}  # game_id -> event of the running job
+        # Held while claiming a job and registering its cancel event, so a
+        # cancel finds every job either still queued or with its event
+        self._claim_lock = asyncio.Lock()
+        self._wakeup = asyncio.Event()
+
+    async def start(self, worker_count: int):
+        """
+        Reclaim interrupted jobs and start the workers.
+
+        Args:
+            worker_count: Number of games analyzed in parallel
+        """
+        async with async_session() as db:
+            reclaimed = await crud_jobs.reclaim_interrupted_jobs(db)
+            if reclaimed:
+                print(f"Reclaimed {reclaimed} interrupted analysis jobs")
+
+        self.resize(worker_count)
+
+    def resize(self, worker_count: int):
+        """
+        Change the number of workers.
+
+        Extra workers exit after finishing their current job. Workers are
+        kept by slot, so a slot freed while a higher one is still busy is
+        filled again.
+
+        Args:
+            worker_count: Number of games analyzed in parallel
+        """
+        self.worker_count = max(1, worker_count)
+        self.workers = {worker_id: task for worker_id, task in self.workers.items() if not task.done()}
+
+        for worker_id in range(self.worker_count):
+            if worker_id not in self.workers:
+                self.workers[worker_id] = asyncio.create_task(self._worker(worker_id))
+
+        self.wake()
+
+    async def stop(self):
+        """Cancel all workers. Running jobs are reclaimed on the next start."""
+        for task in self.workers.values():
+            task.cancel()
+        await asyncio.gather(*self.workers.values(), return_exceptions=True)
+        self.workers = {}
+        self.worker_count = 0
+
+    async def cancel(self, game_ids: Optional[List[int]] = None) -> Dict[str, int]:
+        """
+        Cancel queued jobs and stop running analyses.
+
+        Running analyses stop their current search and keep the moves
+        analysed so far in their checkpoint.
+
+        Args:
+            game_ids: Only cancel analysis of these games (everything if None)
+
+        Returns:
+            Dictionary with stats: {'cancelled': int, 'stopping': int}
+        """
+        async with self._claim_lock:
+            async with async_session() as db:
+                jobs = await crud_jobs.cancel_queued_jobs(db, game_ids)
+
+            # Claimed jobs have their event, including those not started yet
+            stopping = 0
+            for game_id, cancel_event in self.cancel_events.items():
+                if game_ids is None or game_id in game_ids:
+                    cancel_event.set()
+                    stopping += 1
+
+        statuses = {}
+        if jobs:
+            async with async_session() as db:
+                # Games back at 'completed' may have an analysis weaker than the settings
+                await mark_stale_analyses(db)
+                for job in jobs:
+                    game = await crud_games.get_game_by_id(db, job.game_id)
+                    statuses[job.game_id] = game.analysis_status if game else "cancelled"
+
+        for job in jobs:
+            event_broker.publish({
+                "type": "cancelled",
+                "game_id": job.game_id,
+                "job_id": job.id,
+                "analysis_status": statuses[job.game_id]
+            })
+
+        return {"cancelled": len(jobs), "stopping": stopping}
+
+    def wake(self):
+        """Wake idle workers after new jobs were added."""
+        self._wakeup.set()
+
+    async def _wait_for_jobs(self):
+        self._wakeup.clear()
+        try:
+            await asyncio.wait_for(self._wakeup.wait(), timeout=self.poll_interval)
+        except asyncio.TimeoutError:
+            pass
+
+    async def _worker(self, worker_id: int):
+        while worker_id < self.worker_count:
+            try:
+                async with self._claim_lock:
+                    async with async_session() as db:
+                        job = await crud_jobs.claim_next_job(db)
+                    if job is not None:
+                        cancel_event = asyncio.Event()
+                        self.cancel_events[job.game_id] = cancel_event
+
+                if job is None:
+                    await self._wait_for_jobs()
+                    continue
+
+                options = json.loads(job.options) if job.options is not None else None
+                try:
+                    await self._run_job(job.id, job.game_id, cancel_event, job.attempts, options)
+                finally:
+                    self.cancel_events.pop(job.game_id, None)
+
+            except asyncio.CancelledError:
+                raise
+            except Exception as e:
+                print(f"Analysis worker {worker_id} error: {str(e)}")
+                await asyncio.sleep(self.poll_interval)
+
+    async def _run_job(
+        self,
+        job_id: int,
+        game_id: int,
+        cancel_event: asyncio.Event,
+        attempts: int = 1,
+        options: Optional[Dict[str, int]] = None
+    ):
+        """
+        Analyze one game and record the outcome on the job and the game.
+
+        Args:
+            job_id: Database ID of the claimed job
+            game_id: Database ID of the game to analyze
+            cancel_event: Set by cancel() to stop the analysis
+            attempts: Number of times the job has been claimed, including this one
+            options: Setting overrides of a re-analysis job (None for a first analysis)
+        """
+        async with async_session() as db:
+            game = await crud_games.get_game_by_id(db, game_id)
+
+            if not game:
+                await crud_jobs.finish_job(db, job_id, "failed", "Game not found")
+                return
+
+            if options is None and await has_game_analysis(db, game_id):
+                # Analysed in the meantime (e.g. a restored analysis file)
+                await crud_games.update_game_analysis_status(db, game_id, "completed", force=True)
+                await crud_jobs.finish_job(db, job_id, "completed")
+                return
+
+            def on_progress(progress):
+                event_broker.publish({
+                    "type": "progress",
+                    "game_id": game_id,
+                    "job_id": job_id,
+                    "move_index": progress["move_index"],
+                    "total_moves": progress["total_moves"],
+                    "move": progress["move"]
+                })
+
+            try:
+                # Update status to analyzing
+                await crud_games.update_game_analysis_status(db, game_id, "analyzing")
+                event_broker.publish({"type": "started", "game_id": game_id, "job_id": job_id})
+
+                result = await analyze_game_async(
+                    game_id=game_id,
+                    pgn_text=game.pgn,
+                    db=db,
+                    progress_callback=on_progress,
+                    cancel_event=cancel_event,
+                    overrides=options
+                )
+
+                # Update status to completed and store per-player accuracy
+                await crud_games.update_game_analysis_status(db, game_id, "completed")
+                player_stats = result["player_stats"]
+                await crud_games.update_game_accuracy(
+                    db,
+                    game_id,
+                    player_stats["white"]["accuracy"],
+                    player_stats["black"]["accuracy"]
+                )
+                await crud_jobs.finish_job(db, job_id, "completed")
+                event_broker.publish({
+                    "type": "completed",
+                    "game_id": game_id,
+                    "job_id": job_id,
+                    "total_moves": result["total_moves"],
+                    "player_stats": player_stats
+                })
+
+            except AnalysisCancelled as e:
+                # The checkpoint keeps the moves analysed so far
+                status = await self._finish_status(db, game_id, "cancelled")
+                await crud_jobs.finish_job(db, job_id, "cancelled")
+                event_broker.publish({
+                    "type": "cancelled",
+                    "game_id": game_id,
+                    "job_id": job_id,
+                    "analysed_moves": e.analysed_moves,
+                    "analysis_status": status
+                })
+
+            except Exception as e:
+                if attempts < MAX_JOB_ATTEMPTS:
+                    # Retry later - the checkpoint keeps the moves analysed so far. A game
+                    # with a stored analysis still has a valid one, so only the job keeps the error
+                    error = None if await has_game_analysis(db, game_id) else str(e)
+                    await crud_games.update_game_analysis_status(db, game_id, "queued", error=error)
+                    await crud_jobs.requeue_job(db, job_id, str(e))
+                    event_broker.publish({"type": "queued", "game_id": game_id, "job_id": job_id, "error": str(e)})
+                    self.wake()
+                    print(f"Analysis of game {game_id} failed (attempt {attempts}), retrying: {str(e)}")
+                    return
+
+                status = await self._finish_status(db, game_id, "failed", error=str(e))
+                await crud_jobs.finish_job(db, job_id, "failed", str(e))
+                event_broker.publish({
+                    "type": "failed",
+                    "game_id": game_id,
+                    "job_id": job_id,
+                    "error": str(e),
+                    "analysis_status": status
+                })
+                print(f"Analysis of game {game_id} failed: {str(e)}")
+
+    async def _finish_status(self, db: AsyncSession, game_id: int, status: str, error: Optional[str] = None) -> str:
+        """
+        Set the status of a game whose analysis job was cancelled or failed.
+
+        A re-analysis that doesn't finish leaves the stored analysis valid,
+        so the game goes back to 'completed' (or 'stale', if its analysis
+        is weaker than the current settings) and the error is only kept
+        on the job.
+
+        Args:
+            db: Database session
+            game_id: Database ID of the game
+            status: 'cancelled' or 'failed', used when there is no stored analysis
+            error: Error message for 'failed'
+
+        Returns:
+            The status the game ended up in
+        """
+        if not await has_game_analysis(db, game_id):
+            await crud_games.update_game_analysis_status(db, game_id, status, error=error)
+            return status
+
+        await crud_games.update_game_analysis_status(db, game_id, "completed")
+        await mark_stale_analyses(db)
+        game = await crud_games.get_game_by_id(db, game_id)
+        return game.analysis_status
+
+
+# Global worker pool, started in the application lifespan
+worker_pool = AnalysisWorkerPool()
+
+
+async def enqueue_analysis(
+    game_ids: List[int],
+    priority: int = 0,
+    options: Optional[Dict[str, int]] = None
+) -> dict:
+    """
+    Add games to the analysis queue and wake the workers.
+
+    Args:
+        game_ids: Database IDs of the games to analyze
+        priority: Higher priority jobs are picked up first
+        options: Setting overrides; when given, analysed games are analysed
+            again and the result is stored as a new version
+
+    Returns:
+        Dictionary with stats: {'enqueued': int, 'skipped': int, 'total': int}
+    """
+    async with async_session() as db:
+        result = await crud_jobs.enqueue_games(db, game_ids, priority=priority, options=options)
+
+    if result["enqueued"]:
+        event_broker.publish({"type": "queued", "game_id": None, "enqueued": result["enqueued"]})
+
+    worker_pool.wake()
+    return result
@@ -0,0 +1,337 @@
+import io
+from dataclasses import dataclass, asdict
+from typing import Dict, List, Optional, Any
+
+import chess
+import chess.pgn
+
+from .move_classification import score_to_cp, compute_cp_loss, CP_LOSS_EVAL_CAP
+
+
+# all: every ply; mine: only the configured player's moves; range: plies
+# from_ply..to_ply; critical: quick pass over every ply, then a full search
+# of the plies where the evaluation swings; adaptive: like critical, but the
+# second pass also covers unclear classifications and stops at a time budget
+SCOPES = ("all", "mine", "range", "critical", "adaptive")
+
+# Share of an adaptive analysis' time budget spent on the first pass
+ADAPTIVE_SWEEP_SHARE = 0.4
+
+# Lower limit of the first pass' time per move in an adaptive analysis
+ADAPTIVE_MIN_SWEEP_MS = 50
+
+# A centipawn loss this close to a classification threshold (as a fraction
+# of the threshold) may change its classification with a deeper search
+UNCLEAR_THRESHOLD_MARGIN = 0.25
+
+# Job options that select the scope rather than search settings
+SCOPE_OPTIONS = ("scope", "from_ply", "to_ply")
+
+
+@dataclass
+class AnalysisScope:
+    """Which plies of a game get a full engine search."""
+
+    mode: str = "all"
+    color: Optional[str] = None  # "white" or "black" for mode "mine"
+    from_ply: Optional[int] = None  # 0-based and inclusive, for mode "range"
+    to_ply: Optional[int] = None
+    quick_depth: Optional[int] = None  # Depth of the first pass, for modes "critical" and "adaptive"
+    swing_cp: Optional[int] = None  # Evaluation change that makes a ply critical
+    time_budget_ms: Optional[int] = None  # Total search time per game, for mode "adaptive"
+
+    @property
+    def two_pass(self) -> bool:
+        """Whether every ply gets a quick search before the important ones are searched in full."""
+        return self.mode in ("critical", "adaptive")
+
+    def includes(self, ply: int, white_to_move: bool) -> bool:
+        """
+        Check whether a ply is analysed at all.
+
+        Args:
+            ply: 0-based index of the move in the game
+            white_to_move: Whether White plays the move
+
+        Returns:
+            False if the ply is skipped
+        """
+        if self.mode == "mine":
+            return white_to_move == (self.color == "white")
+        if self.mode == "range":
+            return (self.from_ply is None or ply >= self.from_ply) and (self.to_ply is None or ply <= self.to_ply)
+        return True
+
+    def to_dict(self) -> Dict[str, Any]:
+        """
+        Get the scope for the analysis metadata.
+
+        Returns:
+            Dictionary with the mode and only the parameters that apply to it
+        """
+        return {key: value for key, value in asdict(self).items() if value is not None}
+
+    def sweep_time_ms(self, plies: int, time_ms: int) -> int:
+        """
+        Get the time per move of the first pass.
+
+        Adaptive analyses spread ADAPTIVE_SWEEP_SHARE of their budget over
+        the plies, leaving the rest for the second pass.
+
+        Args:
+            plies: Number of plies still to search
+            time_ms: Configured time per move
+
+        Returns:
+            Time per move in milliseconds (never more than time_ms)
+        """
+        if self.mode != "adaptive" or not plies:
+            return time_ms
+        share = int(self.time_budget_ms * ADAPTIVE_SWEEP_SHARE / plies)
+        return min(time_ms, max(ADAPTIVE_MIN_SWEEP_MS, share))
+
+    def full_search_budget_ms(self, sweep_ms: float) -> Optional[float]:
+        """
+        Get the search time left for the second pass of an adaptive analysis.
+
+        Args:
+            sweep_ms: Time the first pass took
+
+        Returns:
+            Time in milliseconds, or None if the scope has no time budget
+        """
+        if self.mode != "adaptive" or self.time_budget_ms is None:
+            return None
+        return max(0.0, self.time_budget_ms - sweep_ms)
+
+
+def validate_scope_options(
+    scope: Optional[str],
+    from_ply: Optional[int],
+    to_ply: Optional[int],
+    username: Optional[str]
+) -> List[str]:
+    """
+    Check the scope options of an analysis request.
+
+    Args:
+        scope: Requested scope (one of SCOPES, None for all plies)
+        from_ply: First ply of a range (0-based)
+        to_ply: Last ply of a range (inclusive)
+        username: Configured Chess.com username
+
+    Returns:
+        List of error messages (empty if the options are valid)
+    """
+    errors = []
+
+    if scope is not None and scope not in SCOPES:
+        errors.append(f"Scope must be one of: {', '.join(SCOPES)}")
+    if scope == "mine" and not username:
+        errors.append("Scope 'mine' needs a Chess.com username in the settings")
+    if scope == "range":
+        if from_ply is None and to_ply is None:
+            errors.append("Scope 'range' needs from_ply and/or to_ply")
+        if (from_ply is not None and from_ply < 0) or (to_ply is not None and to_ply < 0):
+            errors.append("Ply numbers must not be negative")
+        elif from_ply is not None and to_ply is not None and from_ply > to_ply:
+            errors.append("from_ply must not be after to_ply")
+    elif from_ply is not None or to_ply is not None:
+        errors.append("from_ply and to_ply only apply to scope 'range'")
+
+    return errors
+
+
+def resolve_scope(
+    options: Optional[Dict[str, Any]],
+    pgn_text: str,
+    settings: Dict[str, Any]
+) -> AnalysisScope:
+    """
+    Build the scope of an analysis job from its options.
+
+    Without a requested scope, the adaptive pipeline is used if it is enabled
+    in the settings, otherwise every ply is analysed.
+
+    Args:
+        options: Job options with "scope" and, for ranges, "from_ply"/"to_ply"
+        pgn_text: Game in PGN format (to find the configured player's color)
+        settings: Settings dictionary from get_stockfish_settings
+
+    Returns:
+        AnalysisScope
+
+    Raises:
+        ValueError: If the scope is unknown or the configured player didn't play the game
+    """
+    options = options or {}
+    mode = options.get("scope") or ("adaptive" if settings["adaptive_analysis_enabled"] else "all")
+
+    if mode not in SCOPES:
+        raise ValueError(f"Unknown analysis scope '{mode}'")
+
+    if mode == "mine":
+        username = (settings.get("chess_com_username") or "").lower()
+        headers = chess.pgn.read_headers(io.StringIO(pgn_text))
+        for color in ("white", "black"):
+            if username and headers and headers.get(color.capitalize(), "").lower() == username:
+                return AnalysisScope(mode=mode, color=color)
+        raise ValueError(f"The configured player '{username}' did not play this game")
+
+    if mode == "range":
+        return AnalysisScope(mode=mode, from_ply=options.get("from_ply"), to_ply=options.get("to_ply"))
+
+    if mode == "critical":
+        return AnalysisScope(
+            mode=mode,
+            quick_depth=settings["critical_pass_depth"],
+            swing_cp=settings["critical_swing_cp"]
+        )
+
+    if mode == "adaptive":
+        return AnalysisScope(
+            mode=mode,
+            quick_depth=settings["critical_pass_depth"],
+            swing_cp=settings["critical_swing_cp"],
+            time_budget_ms=settings["adaptive_time_budget_s"] * 1000
+        )
+
+    return AnalysisScope()
+
+
+def skipped_move_entry(board: chess.Board, move: chess.Move, move_number: int) -> Dict[str, Any]:
+    """
+    Build the entry of a ply outside the analysis scope.
+
+    Args:
+        board: Position before the move
+        move: The played move
+        move_number: 1-based ply counter used by analyze_game
+
+    Returns:
+        Move entry marked "skipped", without engine data
+    """
+    fen = board.fen()
+    return {
+        "move_number": move_number,
+        "move": board.san(move),
+        "uci": move.uci(),
+        "fen_before": fen,
+        "analysis": {
+            "fen": fen,
+            "best_move": None,
+            "pv": [],
+            "depth": 0,
+            "lines": [],
+            "score": None,
+            "score_type": "skipped",
+            "score_value": None
+        },
+        "played_move_analysis": None,
+        "book": False,
+        "skipped": True
+    }
+
+
+def _evaluation_swings(
+    moves: List[Dict[str, Any]],
+    final_analysis: Optional[Dict[str, Any]]
+) -> Dict[int, int]:
+    """
+    Get the evaluation change of every searched ply.
+
+    The evaluation after a move is the played move's own score, or the
+    next position's score. Evaluations are clamped like for the
+    centipawn loss, so a won position getting "more won" doesn't count.
+
+    Args:
+        moves: Move entries of the first pass
+        final_analysis: Analysis of the final position
+
+    Returns:
+        Signed change (White's perspective) keyed by 0-based ply index;
+        book and skipped plies and plies without a score are left out
+    """
+    def clamped(analysis):
+        cp = score_to_cp(analysis)
+        return None if cp is None else max(-CP_LOSS_EVAL_CAP, min(CP_LOSS_EVAL_CAP, cp))
+
+    swings = {}
+    for index, move in enumerate(moves):
+        if move.get("book") or move.get("skipped"):
+            continue
+
+        next_analysis = moves[index + 1]["analysis"] if index + 1 < len(moves) else final_analysis
+        before = clamped(move.get("analysis"))
+        after = clamped(move.get("played_move_analysis") or next_analysis)
+
+        if before is not None and after is not None:
+            swings[index] = after - before
+
+    return swings
+
+
+def find_critical_plies(
+    moves: List[Dict[str, Any]],
+    final_analysis: Optional[Dict[str, Any]],
+    swing_cp: int
+) -> List[int]:
+    """
+    Find the plies where the evaluation swings after a quick first pass.
+
+    Args:
+        moves: Move entries of the first pass
+        final_analysis: Analysis of the final position
+        swing_cp: Minimum evaluation change in centipawns
+
+    Returns:
+        0-based indexes of the critical plies
+    """
+    swings = _evaluation_swings(moves, final_analysis)
+    return [index for index, swing in swings.items() if abs(swing) >= swing_cp]
+
+
+def find_adaptive_plies(
+    moves: List[Dict[str, Any]],
+    final_analysis: Optional[Dict[str, Any]],
+    swing_cp: int,
+    thresholds: Dict[str, int]
+) -> List[int]:
+    """
+    Find the plies worth a second, full search, most important first.
+
+    Plies where the evaluation swings come first, largest swing first.
+    They are followed by plies whose centipawn loss lies within
+    UNCLEAR_THRESHOLD_MARGIN of an inaccuracy, mistake or blunder threshold,
+    closest first, as a deeper search may change their classification.
+
+    Args:
+        moves: Move entries of the first pass
+        final_analysis: Analysis of the final position
+        swing_cp: Minimum evaluation change in centipawns
+        thresholds: Classification thresholds in centipawns
+
+    Returns:
+        0-based indexes of the plies in the order they should be searched
+    """
+    swings = _evaluation_swings(moves, final_analysis)
+
+    critical = sorted(
+        (index for index, swing in swings.items() if abs(swing) >= swing_cp),
+        key=lambda index: -abs(swings[index])
+    )
+
+    unclear = {}
+    for index, swing in swings.items():
+        if abs(swing) >= swing_cp:
+            continue
+        white_moved = moves[index]["fen_before"].split()[1] == "w"
+        cp_loss = compute_cp_loss(0, swing, white_moved)
+        for label in ("inaccuracy", "mistake", "blunder"):
+            if thresholds[label] <= 0:
+                continue
+            distance = abs(cp_loss - thresholds[label]) / thresholds[label]
+            if distance <= UNCLEAR_THRESHOLD_MARGIN:
+                unclear[index] = min(distance, unclear.get(index, distance))
+
+    return critical + sorted(unclear, key=lambda index: unclear[index])
@@ -0,0 +1,192 @@
+import hashlib
+import json
+from typing import Dict, Any, Optional
+
+from sqlalchemy.ext.asyncio import AsyncSession
+
+from ..db.models import Analysis
+from ..crud import games as crud_games
+from ..crud import analyses as crud_analyses
+from ..crud import analysis_presets as crud_presets
+from ..crud import engines as crud_engines
+from .analysis_presets import TIME_CLASSES, default_preset_name, preset_overrides
+from .engine_profiles import apply_engine_profile
+from .analysis_scope import AnalysisScope
+
+
+# Settings whose change can make existing analyses outdated
+STALENESS_SETTINGS = {
+    "stockfish_path",
+    "analysis_depth",
+    "analysis_time_ms",
+    "analysis_multipv",
+    "analysis_limit_mode",
+    "analysis_nodes",
+    "analysis_mate_moves",
+    "deterministic_mode",
+    "default_engine",
+    "adaptive_analysis_enabled",
+    "critical_pass_depth",
+    *(f"preset_{time_class}" for time_class in TIME_CLASSES)
+}
+
+
+def current_search_limit(settings: Dict[str, Any]) -> Dict[str, Any]:
+    """
+    Get the search limit an analysis with these settings would use.
+
+    Args:
+        settings: Settings dictionary from get_stockfish_settings
+
+    Returns:
+        Dictionary from search_limit, deterministic mode applied
+    """
+    from .stockfish_service import apply_deterministic_mode, search_limit
+
+    settings = apply_deterministic_mode(settings)
+    return search_limit(
+        settings["analysis_limit_mode"],
+        settings["analysis_depth"],
+        settings["analysis_time_ms"],
+        settings["analysis_nodes"],
+        settings["analysis_mate_moves"]
+    )
+
+
+def current_quick_depth(settings: Dict[str, Any]) -> Optional[int]:
+    """
+    Get the quick-pass depth an analysis with these settings would use.
+
+    Args:
+        settings: Settings dictionary from get_stockfish_settings; an
+            "analysis_scope" entry (set for a running analysis) takes
+            precedence over the default scope
+
+    Returns:
+        Depth of the first pass, or None if every ply gets a full search
+    """
+    scope = settings.get("analysis_scope") or {
+        "mode": "adaptive" if settings["adaptive_analysis_enabled"] else "all"
+    }
+    if not AnalysisScope(mode=scope["mode"]).two_pass:
+        return None
+    return scope.get("quick_depth", settings["critical_pass_depth"])
+
+
+def settings_fingerprint(settings: Dict[str, Any]) -> str:
+    """
+    Hash the engine and search settings that determine an analysis' quality.
+
+    Args:
+        settings: Settings dictionary from get_stockfish_settings (overrides applied)
+
+    Returns:
+        Short hex digest; equal settings give equal fingerprints
+    """
+    relevant = {
+        "engine_path": settings["stockfish_path"],
+        "multipv": settings["analysis_multipv"],
+        "limit": current_search_limit(settings),
+        "deterministic": settings["deterministic_mode"]
+    }
+    if settings.get("engine_options"):
+        relevant["engine_options"] = settings["engine_options"]
+    quick_depth = current_quick_depth(settings)
+    if quick_depth is not None:
+        relevant["quick_depth"] = quick_depth
+    return hashlib.sha1(json.dumps(relevant, sort_keys=True).encode()).hexdigest()[:16]
+
+
+def is_analysis_weaker(analysis: Analysis, settings: Dict[str, Any]) -> bool:
+    """
+    Check whether an analysis falls short of the current configuration.
+
+    An analysis is weaker when it was made with another engine binary, another
+    limit mode or deterministic setting, or with a lower limit (depth, time,
+    nodes, mate distance) or number of lines than currently configured.
+    Two-pass analyses (critical or adaptive scope) are weaker than a full
+    analysis and than a two-pass analysis with a deeper quick pass.
+    Analyses from before the limit mode was recorded count as depth and time
+    limited; they are only compared on the values they have.
+
+    Args:
+        analysis: Stored analysis
+        settings: Current settings from get_stockfish_settings
+
+    Returns:
+        True if the analysis should be redone
+    """
+    if analysis.settings_fingerprint and analysis.settings_fingerprint == settings_fingerprint(settings):
+        return False
+
+    if analysis.engine_path and analysis.engine_path != settings["stockfish_path"]:
+        return True
+
+    stored = json.loads(analysis.settings) if analysis.settings else {}
+    stored_limit = stored.get("limit") or {"mode": "depth_time"}
+    limit = current_search_limit(settings)
+
+    if stored_limit["mode"] != limit["mode"] or stored.get("deterministic", False) != settings["deterministic_mode"]:
+        return True
+
+    # Analyses from before two_pass was recorded still have their scope
+    stored_scope = stored.get("scope") or {}
+    if stored.get("two_pass", AnalysisScope(mode=stored_scope.get("mode", "all")).two_pass):
+        wanted_quick_depth = current_quick_depth(settings)
+        stored_quick_depth = stored.get("quick_depth") or stored_scope.get("quick_depth") or 0
+        if wanted_quick_depth is None or stored_quick_depth < wanted_quick_depth:
+            return True
+
+    stored_values = {"depth": analysis.depth, "time_ms": analysis.time_ms, "multipv": analysis.multipv, **stored_limit}
+    wanted = {**limit, "multipv": settings["analysis_multipv"]}
+    for field, value in wanted.items():
+        if field != "mode" and stored_values.get(field) is not None and stored_values[field] < value:
+            return True
+
+    return False
+
+
+async def mark_stale_analyses(db: AsyncSession) -> Dict[str, int]:
+    """
+    Compare every completed analysis with the current settings.
+
+    Each game is compared with the settings it would be analysed with now,
+    i.e. including the default preset of its time class, and with the engine
+    profile of its latest analysis (or else the default engine). Completed games
+    whose latest analysis is weaker become 'stale'; stale games whose
+    analysis matches the settings again (e.g. after reverting a change)
+    go back to 'completed'.
+
+    Args:
+        db: Database session
+
+    Returns:
+        Dictionary with the number of games marked stale and restored
+    """
+    from .stockfish_service import get_stockfish_settings, apply_setting_overrides
+
+    settings = await get_stockfish_settings(db)
+    presets = {preset.name: preset for preset in await crud_presets.get_presets(db)}
+    engines = {engine.name: crud_engines.engine_to_dict(engine) for engine in await crud_engines.get_engines(db)}
+    default_engine = engines.get(settings["default_engine"])
+    completed = await crud_games.get_games_by_analysis_status(db, "completed")
+    stale = await crud_games.get_games_by_analysis_status(db, "stale")
+    latest = await crud_analyses.get_latest_analyses(db, [game.id for game in completed + stale])
+
+    def weaker(game) -> bool:
+        if game.id not in latest:
+            return False
+        analysis = latest[game.id]
+        preset = presets.get(default_preset_name(game.pgn, settings))
+        game_settings = apply_setting_overrides(settings, preset_overrides(preset)) if preset else settings
+        # A profile chosen for the analysis stays the reference while it exists
+        game_settings = apply_engine_profile(game_settings, engines.get(analysis.engine_profile) or default_engine)
+        return is_analysis_weaker(analysis, game_settings)
+
+    newly_stale = [game for game in completed if weaker(game)]
+    restored = [game for game in stale if game.id in latest and not weaker(game)]
+
+    return {
+        "stale": await crud_games.update_analysis_status_many(db, newly_stale, "stale"),
+        "restored": await crud_games.update_analysis_status_many(db, restored, "completed")
+    }
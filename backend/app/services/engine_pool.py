@@ -0,0 +1,183 @@
+import asyncio
+from typing import Dict, List, Any
+
+from .stockfish_service import StockfishAnalyzer
+
+
+class EnginePool:
+    """
+    Pool of long-lived, warmed-up Stockfish engines.
+
+    Engines are started once and reused across games, so the UCI handshake
+    and hash allocation happen only when the pool grows or when engine
+    options (path, threads, hash, tablebases, profile options) change.
+    """
+
+    def __init__(self, size: int = 1):
+        """
+        Initialize the engine pool.
+
+        Args:
+            size: Maximum number of engine processes kept alive
+        """
+        self.size = max(1, size)
+        self.idle: List[StockfishAnalyzer] = []
+        self.in_use = 0
+        self._condition = asyncio.Condition()
+
+    async def acquire(self, settings: Dict[str, Any]) -> StockfishAnalyzer:
+        """
+        Take an engine configured for the given settings, waiting if all are busy.
+
+        An idle engine started with different engine options is stopped and
+        replaced by a fresh one.
+
+        Args:
+            settings: Settings dictionary from get_stockfish_settings
+
+        Returns:
+            Running StockfishAnalyzer ready for a new game
+        """
+        async with self._condition:
+            while not self.idle and self.in_use >= self.size:
+                await self._condition.wait()
+
+            analyzer = self.idle.pop() if self.idle else None
+            self.in_use += 1
+
+        try:
+            wanted_key = (
+                settings["stockfish_path"],
+                settings["stockfish_threads"],
+                settings["stockfish_hash"],
+                settings["syzygy_path"],
+                settings["syzygy_probe_limit"],
+                tuple(sorted(settings["engine_options"].items()))
+            )
+
+            if analyzer is not None and (analyzer.engine_key != wanted_key or not analyzer.is_running()):
+                await analyzer.stop_engine()
+                analyzer = None
+
+            if analyzer is None:
+                analyzer = StockfishAnalyzer(
+                    stockfish_path=settings["stockfish_path"],
+                    threads=settings["stockfish_threads"],
+                    hash_mb=settings["stockfish_hash"],
+                    depth=settings["analysis_depth"],
+                    time_ms=settings["analysis_time_ms"],
+                    multipv=settings["analysis_multipv"],
+                    classification_thresholds=settings["classification_thresholds"],
+                    position_retries=settings["analysis_position_retries"],
+                    syzygy_path=settings["syzygy_path"],
+                    syzygy_probe_limit=settings["syzygy_probe_limit"],
+                    uci_options=settings["engine_options"]
+                )
+                await analyzer.start_engine()
+
+            await analyzer.configure_search(
+                settings["analysis_depth"],
+                settings["analysis_time_ms"],
+                settings["analysis_multipv"],
+                settings["classification_thresholds"],
+                settings["analysis_position_retries"],
+                limit_mode=settings["analysis_limit_mode"],
+                nodes=settings["analysis_nodes"],
+                mate_moves=settings["analysis_mate_moves"],
+                deterministic=settings["deterministic_mode"],
+                search_timeout_s=settings["analysis_search_timeout_s"]
+            )
+            return analyzer
+
+        except BaseException:
+            # Also on cancellation, or the slot would never be freed; the
+            # engine may be half configured, so it isn't reused
+            try:
+                if analyzer is not None:
+                    await analyzer.stop_engine()
+            finally:
+                await self._release_slot()
+            raise
+
+    async def release(self, analyzer: StockfishAnalyzer, discard: bool = False):
+        """
+        Return an engine to the pool.
+
+        The engine is reset with ucinewgame; engines that crashed, were
+        discarded, or exceed the current pool size are stopped instead.
+
+        Args:
+            analyzer: Engine obtained from acquire()
+            discard: Stop the engine instead of reusing it (e.g. after an error)
+        """
+        keep = not discard and analyzer.is_running()
+
+        if keep:
+            try:
+                await analyzer.new_game()
+            except Exception:
+                keep = False
+
+        async with self._condition:
+            self.in_use -= 1
+            if keep and len(self.idle) + self.in_use < self.size:
+                self.idle.append(analyzer)
+                analyzer = None
+            self._condition.notify()
+
+        if analyzer is not None:
+            await analyzer.stop_engine()
+
+    async def _release_slot(self):
+        async with self._condition:
+            self.in_use -= 1
+            self._condition.notify()
+
+    async def resize(self, size: int):
+        """
+        Change the maximum number of engines, stopping surplus idle engines.
+
+        Args:
+            size: Maximum number of engine processes kept alive
+        """
+        async with self._condition:
+            self.size = max(1, size)
+            surplus = []
+            while self.idle and len(self.idle) + self.in_use > self.size:
+                surplus.append(self.idle.pop())
+            self._condition.notify_all()
+
+        for analyzer in surplus:
+            await analyzer.stop_engine()
+
+    async def recycle(self):
+        """
+        Stop all idle engines so the next games start with fresh settings.
+        Engines currently analysing are replaced when they are next acquired.
+        """
+        async with self._condition:
+            stale, self.idle = self.idle, []
+
+        for analyzer in stale:
+            await analyzer.stop_engine()
+
+    async def shutdown(self):
+        """Stop all idle engines (called on application shutdown)."""
+        await self.recycle()
+
+    def stats(self) -> Dict[str, int]:
+        """
+        Get the current pool usage.
+
+        Returns:
+            Dictionary with pool size, idle and in-use engine counts
+        """
+        return {
+            "size": self.size,
+            "idle": len(self.idle),
+            "in_use": self.in_use
+        }
+
+
+# Global engine pool shared by all analysis jobs
+engine_pool = EnginePool()
@@ -0,0 +1,90 @@
+from typing import Dict, Any, Optional, Tuple
+
+from sqlalchemy.ext.asyncio import AsyncSession
+
+from ..crud import engines as crud_engines
+from .uci_client import UciClient
+
+
+# Options the analyzer sets itself from the settings and analysis options
+MANAGED_OPTIONS = {"threads", "hash", "multipv", "uci_showwdl", "syzygypath", "syzygyprobelimit"}
+
+
+async def identify_engine(path: str, uci_options: Optional[Dict[str, Any]] = None) -> Dict[str, Optional[str]]:
+    """
+    Start an engine once to read its identity and check its options.
+
+    Args:
+        path: Path of the engine binary
+        uci_options: UCI options of the profile, checked against the engine's option list
+
+    Returns:
+        Dictionary with the "id_name" and "id_author" the engine reported
+
+    Raises:
+        ValueError: If the engine doesn't start or doesn't know an option
+    """
+    client = UciClient(path)
+    try:
+        await client.start()
+    except Exception as e:
+        raise ValueError(f"Could not start engine '{path}': {str(e)}")
+    finally:
+        await client.quit()
+
+    # UCI option names are case-insensitive
+    known = {option.lower() for option in client.options}
+    for name in uci_options or {}:
+        if name.lower() in MANAGED_OPTIONS:
+            raise ValueError(f"Option '{name}' is set from the analysis settings")
+        if name.lower() not in known:
+            raise ValueError(f"{client.name or 'The engine'} has no option '{name}'")
+
+    return {"id_name": client.name, "id_author": client.author}
+
+
+def apply_engine_profile(settings: Dict[str, Any], engine: Optional[Dict[str, Any]]) -> Dict[str, Any]:
+    """
+    Run an analysis with an engine profile instead of the stockfish_path setting.
+
+    Args:
+        settings: Settings dictionary from get_stockfish_settings
+        engine: Profile dictionary from engine_to_dict (None keeps the settings)
+
+    Returns:
+        New settings dictionary with the profile's path, options and name
+    """
+    settings = dict(settings)
+    if engine is not None:
+        settings["stockfish_path"] = engine["path"]
+        settings["engine_options"] = engine["uci_options"]
+        settings["engine_profile"] = engine["name"]
+    return settings
+
+
+async def resolve_engine_profile(
+    db: AsyncSession,
+    name: Optional[str],
+    settings: Dict[str, Any]
+) -> Tuple[Dict[str, Any], Optional[str]]:
+    """
+    Apply the engine profile requested for a job, or else the default profile.
+
+    Args:
+        db: Database session
+        name: Requested profile name (None for the default_engine setting)
+        settings: Settings dictionary from get_stockfish_settings
+
+    Returns:
+        Tuple of the settings and the applied profile name (None for the stockfish_path setting)
+    """
+    name = name or settings["default_engine"] or None
+    if not name:
+        return settings, None
+
+    engine = await crud_engines.get_engine_by_name(db, name)
+    if engine is None:
+        print(f"Engine profile '{name}' not found, using the Stockfish path setting")
+        return settings, None
+
+    return apply_engine_profile(settings, crud_engines.engine_to_dict(engine)), name
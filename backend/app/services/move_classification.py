@@ -0,0 +1,293 @@
+from typing import Dict, List, Optional, Any
+import chess
+
+from .tablebase import tablebase_outcome
+
+
+# Centipawn value used for forced mates; mate in N maps to MATE_SCORE_CP - N * 100
+MATE_SCORE_CP = 10000
+
+# Evaluations are clamped to this range before computing centipawn loss,
+# so converting +12 into +9 (or M3 into +15) is not reported as a blunder
+CP_LOSS_EVAL_CAP = 1000
+
+CLASSIFICATIONS = ["book", "brilliant", "best", "good", "inaccuracy", "mistake", "blunder"]
+
+# Moves that lose less expected score than this (percentage points, from the
+# engine's WDL statistics) are never worse than "good", whatever the cp loss
+WDL_NEGLIGIBLE_LOSS = 2.0
+
+DEFAULT_THRESHOLDS = {
+    "best": 10,
+    "inaccuracy": 50,
+    "mistake": 100,
+    "blunder": 300
+}
+
+PIECE_VALUES = {
+    chess.PAWN: 1,
+    chess.KNIGHT: 3,
+    chess.BISHOP: 3,
+    chess.ROOK: 5,
+    chess.QUEEN: 9,
+    chess.KING: 0
+}
+
+
+def score_to_cp(analysis: Optional[Dict[str, Any]]) -> Optional[int]:
+    """
+    Convert a position analysis score to centipawns from White's perspective.
+
+    Mate scores are mapped to large centipawn values so that a faster mate
+    is always worth more than a slower one.
+
+    Args:
+        analysis: Position analysis as produced by StockfishAnalyzer.analyze_position
+
+    Returns:
+        Centipawn score from White's perspective, or None if no usable score
+    """
+    if not analysis or analysis.get("score_type") not in ("cp", "mate"):
+        return None
+
+    value = analysis.get("score_value")
+    if value is None:
+        return None
+
+    if analysis["score_type"] == "cp":
+        return int(value)
+
+    if value == 0:
+        # "mate 0" means the side to move is already checkmated
+        fen_parts = (analysis.get("fen") or "").split()
+        white_to_move = len(fen_parts) < 2 or fen_parts[1] == "w"
+        return -MATE_SCORE_CP if white_to_move else MATE_SCORE_CP
+
+    sign = 1 if value > 0 else -1
+    return sign * (MATE_SCORE_CP - abs(value) * 100)
+
+
+def wdl_expected_score(wdl: Dict[str, int]) -> float:
+    """
+    Convert WDL statistics into White's expected score.
+
+    Args:
+        wdl: Win, draw and loss per mille from White's perspective
+
+    Returns:
+        Expected score for White in percent (a draw counts half)
+    """
+    total = wdl["win"] + wdl["draw"] + wdl["loss"]
+    if total <= 0:
+        return 50.0
+    return 100 * (wdl["win"] + wdl["draw"] / 2) / total
+
+
+def compute_cp_loss(eval_before: int, eval_after: int, white_moved: bool) -> int:
+    """
+    Compute how many centipawns the moving side lost with its move.
+
+    Args:
+        eval_before: Evaluation before the move (White's perspective)
+        eval_after: Evaluation after the move (White's perspective)
+        white_moved: True if White made the move
+
+    Returns:
+        Non-negative centipawn loss
+    """
+    before = max(-CP_LOSS_EVAL_CAP, min(CP_LOSS_EVAL_CAP, eval_before))
+    after = max(-CP_LOSS_EVAL_CAP, min(CP_LOSS_EVAL_CAP, eval_after))
+
+    loss = before - after if white_moved else after - before
+    return max(0, loss)
+
+
+def is_sacrifice(fen_before: str, uci_move: str) -> bool:
+    """
+    Check whether a move leaves the moved piece en prise.
+
+    A piece counts as sacrificed when it can be captured by a cheaper piece,
+    or when it is attacked and not defended at all. Pawn moves never count.
+
+    Args:
+        fen_before: Position before the move
+        uci_move: Move in UCI format
+
+    Returns:
+        True if the move gives up material
+    """
+    board = chess.Board(fen_before)
+    move = chess.Move.from_uci(uci_move)
+    piece = board.piece_at(move.from_square)
+
+    if piece is None or piece.piece_type in (chess.PAWN, chess.KING):
+        return False
+
+    captured = board.piece_at(move.to_square)
+    captured_value = PIECE_VALUES[captured.piece_type] if captured else 0
+    moved_value = PIECE_VALUES[piece.piece_type]
+
+    board.push(move)
+    opponent = board.turn
+    attackers = board.attackers(opponent, move.to_square)
+
+    if not attackers or moved_value <= captured_value:
+        return False
+
+    cheapest_attacker = min(PIECE_VALUES[board.piece_type_at(sq)] or 1 for sq in attackers)
+    defended = bool(board.attackers(not opponent, move.to_square))
+
+    return cheapest_attacker < moved_value or not defended
+
+
+def classify_move(
+    cp_loss: int,
+    played_best: bool,
+    thresholds: Dict[str, int],
+    sacrifice: bool = False,
+    eval_after_for_mover: Optional[int] = None
+) -> str:
+    """
+    Classify a move from its centipawn loss.
+
+    Args:
+        cp_loss: Centipawn loss of the move
+        played_best: True if the move matches the engine's best move
+        thresholds: Centipawn thresholds (best/inaccuracy/mistake/blunder)
+        sacrifice: True if the move gives up material
+        eval_after_for_mover: Evaluation after the move from the mover's perspective
+
+    Returns:
+        Classification label
+    """
+    is_best = played_best or cp_loss <= thresholds["best"]
+
+    if is_best and sacrifice and eval_after_for_mover is not None and eval_after_for_mover >= 0:
+        return "brilliant"
+    if is_best:
+        return "best"
+    if cp_loss < thresholds["inaccuracy"]:
+        return "good"
+    if cp_loss < thresholds["mistake"]:
+        return "inaccuracy"
+    if cp_loss < thresholds["blunder"]:
+        return "mistake"
+    return "blunder"
+
+
+def classify_tablebase_move(outcome_before: int, outcome_after: int, white_moved: bool) -> str:
+    """
+    Classify a move between two tablebase-resolved positions.
+
+    Any move that keeps the exact result is best; giving away a win or a
+    draw is a blunder, however small the engine score change looks.
+
+    Args:
+        outcome_before: Result before the move (1 White wins, 0 draw, -1 Black wins)
+        outcome_after: Result after the move
+        white_moved: True if White made the move
+
+    Returns:
+        Classification label
+    """
+    sign = 1 if white_moved else -1
+    return "blunder" if outcome_after * sign < outcome_before * sign else "best"
+
+
+def classify_moves(
+    moves: List[Dict[str, Any]],
+    final_analysis: Optional[Dict[str, Any]],
+    thresholds: Dict[str, int]
+) -> Dict[str, Dict[str, int]]:
+    """
+    Annotate every move with its centipawn loss and classification.
+
+    The evaluation after a move is the engine's score of the played move in
+    the same search as the best move ("played_move_analysis"). For analyses
+    without it, the analysis of the next ply is used, and final_analysis for
+    the last move of the game. Moves are updated in place with "cp_loss" and
+    "classification" keys, plus a "refutation" line for inaccuracies and worse. Opening book moves
+    are classified as "book" without a centipawn loss. When the positions
+    before and after a move are both tablebase-resolved, the move is judged
+    by whether it changed the exact result instead of by the engine score.
+    Moves that barely change the expected score according to the engine's
+    WDL statistics are at worst "good".
+
+    Args:
+        moves: Move entries from StockfishAnalyzer.analyze_game
+        final_analysis: Analysis of the final position of the game
+        thresholds: Centipawn thresholds (best/inaccuracy/mistake/blunder)
+
+    Returns:
+        Per-player counts of each classification
+    """
+    summary = {
+        "white": {label: 0 for label in CLASSIFICATIONS},
+        "black": {label: 0 for label in CLASSIFICATIONS}
+    }
+
+    for index, move in enumerate(moves):
+        white_moved = move["fen_before"].split()[1] == "w"
+
+        if move.get("book"):
+            move["cp_loss"] = None
+            move["classification"] = "book"
+            summary["white" if white_moved else "black"]["book"] += 1
+            continue
+
+        next_analysis = moves[index + 1]["analysis"] if index + 1 < len(moves) else final_analysis
+        played = move.get("played_move_analysis")
+        after_analysis = played or next_analysis
+        eval_before = score_to_cp(move.get("analysis"))
+        eval_after = score_to_cp(after_analysis)
+
+        outcome_before = tablebase_outcome(move.get("analysis"))
+        outcome_after = tablebase_outcome(next_analysis)
+
+        if outcome_before is not None and outcome_after is not None:
+            move["cp_loss"] = (
+                compute_cp_loss(eval_before, eval_after, white_moved)
+                if eval_before is not None and eval_after is not None else None
+            )
+            move["classification"] = classify_tablebase_move(outcome_before, outcome_after, white_moved)
+            summary["white" if white_moved else "black"][move["classification"]] += 1
+            continue
+
+        if eval_before is None or eval_after is None:
+            move["cp_loss"] = None
+            move["classification"] = None
+            continue
+
+        cp_loss = compute_cp_loss(eval_before, eval_after, white_moved)
+        played_best = move["analysis"].get("best_move") == move["uci"]
+
+        move["cp_loss"] = cp_loss
+        move["classification"] = classify_move(
+            cp_loss,
+            played_best,
+            thresholds,
+            sacrifice=is_sacrifice(move["fen_before"], move["uci"]),
+            eval_after_for_mover=eval_after if white_moved else -eval_after
+        )
+
+        # A pawn lost in a dead-drawn endgame doesn't change the expected result
+        wdl_before = move["analysis"].get("wdl")
+        wdl_after = after_analysis.get("wdl")
+        if wdl_before and wdl_after:
+            expected_before = wdl_expected_score(wdl_before)
+            expected_after = wdl_expected_score(wdl_after)
+            move["wdl_loss"] = round(max(0.0, (expected_before - expected_after) * (1 if white_moved else -1)), 1)
+
+            if move["wdl_loss"] < WDL_NEGLIGIBLE_LOSS and move["classification"] in ("inaccuracy", "mistake", "blunder"):
+                move["classification"] = "good"
+
+        # The opponent's best reply to the played move shows what it allows
+        if move["classification"] in ("inaccuracy", "mistake", "blunder") and played and len(played.get("pv", [])) > 1:
+            move["refutation"] = {
+                "pv": played["pv"][1:],
+                "pv_san": played.get("pv_san", [])[1:]
+            }
+
+        summary["white" if white_moved else "black"][move["classification"]] += 1
+
+    return summary
@@ -0,0 +1,209 @@
+import csv
+import io
+from pathlib import Path
+from typing import Dict, Optional, Set, Any
+
+import chess
+import chess.pgn
+import chess.polyglot
+from sqlalchemy.ext.asyncio import AsyncSession
+
+from ..crud import games as crud_games
+
+
+# Bundled ECO table: tab-separated eco, name and SAN movetext
+ECO_TABLE_PATH = Path(__file__).resolve().parent.parent / "resources" / "eco_openings.tsv"
+
+# Openings are only looked for in the first moves of a game
+MAX_OPENING_PLY = 40
+
+
+class OpeningClassifier:
+    """
+    Classifies games by ECO code and opening name.
+
+    Openings are indexed by the position they reach rather than by move
+    order, so a game that transposes into a known line is still recognised.
+    """
+
+    def __init__(self, table_path: Path = ECO_TABLE_PATH):
+        """
+        Initialize the classifier. The table is loaded on first use.
+
+        Args:
+            table_path: Path of the ECO table (TSV with eco, name, pgn columns)
+        """
+        self.table_path = table_path
+        self._positions: Optional[Dict[str, Dict[str, str]]] = None
+        self._line_positions: Optional[Set[str]] = None
+
+    def _load(self):
+        positions = {}
+        line_positions = set()
+
+        with open(self.table_path, 'r', encoding='utf-8') as f:
+            for row in csv.DictReader(f, delimiter='\t'):
+                game = chess.pgn.read_game(io.StringIO(row["pgn"]))
+                if game is None or game.errors:
+                    print(f"Skipping invalid ECO entry {row['eco']} {row['name']}")
+                    continue
+
+                board = game.board()
+                for move in game.mainline_moves():
+                    board.push(move)
+                    line_positions.add(board.epd())
+
+                positions[board.epd()] = {"eco": row["eco"], "name": row["name"]}
+
+        self._positions = positions
+        self._line_positions = line_positions
+
+    @property
+    def positions(self) -> Dict[str, Dict[str, str]]:
+        """Known opening positions keyed by EPD."""
+        if self._positions is None:
+            self._load()
+        return self._positions
+
+    @property
+    def line_positions(self) -> Set[str]:
+        """EPDs of every position along the known opening lines."""
+        if self._line_positions is None:
+            self._load()
+        return self._line_positions
+
+    def classify(self, pgn_text: str) -> Optional[Dict[str, str]]:
+        """
+        Find the deepest known opening position reached in a game.
+
+        Args:
+            pgn_text: Game in PGN format
+
+        Returns:
+            Dictionary with eco and name, or None if no known opening was reached
+        """
+        game = chess.pgn.read_game(io.StringIO(pgn_text))
+        if game is None:
+            return None
+
+        # Games from a custom start position can't follow an opening line
+        if game.headers.get("SetUp") == "1" or "FEN" in game.headers:
+            return None
+
+        board = game.board()
+        opening = None
+
+        for ply, move in enumerate(game.mainline_moves()):
+            if ply >= MAX_OPENING_PLY:
+                break
+            board.push(move)
+            opening = self.positions.get(board.epd(), opening)
+
+        return opening
+
+
+# Global classifier shared by sync and the startup backfill
+opening_classifier = OpeningClassifier()
+
+
+class OpeningBook:
+    """
+    Decides which moves at the start of a game are opening theory.
+
+    A move is a book move if it reaches a position along a line of the
+    bundled ECO table, or if it is listed for the current position in a Polyglot book.
+    """
+
+    def __init__(
+        self,
+        max_ply: int,
+        polyglot_path: Optional[str] = None,
+        classifier: OpeningClassifier = opening_classifier
+    ):
+        """
+        Initialize the opening book.
+
+        Args:
+            max_ply: Plies after which no move counts as a book move
+            polyglot_path: Optional path of a Polyglot .bin book
+            classifier: Classifier providing the bundled ECO positions
+        """
+        self.max_ply = max_ply
+        self.polyglot_path = polyglot_path or None
+        self.classifier = classifier
+        self._reader = None
+
+    def __enter__(self):
+        if self.polyglot_path:
+            self._reader = chess.polyglot.open_reader(self.polyglot_path)
+        return self
+
+    def __exit__(self, *exc_info):
+        if self._reader is not None:
+            self._reader.close()
+            self._reader = None
+
+    def is_book_move(self, board: chess.Board, move: chess.Move) -> bool:
+        """
+        Check whether a move is opening theory.
+
+        Args:
+            board: Position before the move
+            move: Move played in that position
+
+        Returns:
+            True if the move is in the book and within max_ply
+        """
+        if board.ply() >= self.max_ply:
+            return False
+
+        if self._reader is not None and any(entry.move == move for entry in self._reader.find_all(board)):
+            return True
+
+        board.push(move)
+        try:
+            return board.epd() in self.classifier.line_positions
+        finally:
+            board.pop()
+
+
+def classify_opening(pgn_text: str) -> Dict[str, Any]:
+    """
+    Classify a game's opening.
+
+    Args:
+        pgn_text: Game in PGN format
+
+    Returns:
+        Dictionary with eco and opening_name; both are empty strings
+        for games that don't reach a known opening
+    """
+    try:
+        opening = opening_classifier.classify(pgn_text)
+    except Exception as e:
+        print(f"Error classifying opening: {str(e)}")
+        opening = None
+
+    return {
+        "eco": opening["eco"] if opening else "",
+        "opening_name": opening["name"] if opening else ""
+    }
+
+
+async def backfill_game_openings(db: AsyncSession) -> int:
+    """
+    Classify the opening of games imported before openings were tracked.
+
+    Args:
+        db: Database session
+
+    Returns:
+        Number of games that were classified
+    """
+    games = await crud_games.get_games_missing_opening(db)
+
+    for game in games:
+        opening = classify_opening(game.pgn)
+        await crud_games.update_game_opening(db, game.id, opening["eco"], opening["opening_name"])
+
+    return len(games)
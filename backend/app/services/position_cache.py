@@ -0,0 +1,199 @@
+import io
+import json
+import hashlib
+from typing import Dict, List, Any
+
+import chess
+import chess.pgn
+from sqlalchemy.ext.asyncio import AsyncSession
+
+from ..crud import position_cache as crud_position_cache
+
+
+def normalize_fen(fen: str) -> str:
+    """
+    Reduce a FEN to the parts that affect the evaluation.
+
+    Move counters are dropped and the en passant square is only kept
+    when a capture is actually possible, so transpositions share an entry.
+
+    Args:
+        fen: Position in FEN notation
+
+    Returns:
+        Normalized FEN (EPD without operations)
+    """
+    return chess.Board(fen).epd()
+
+
+def engine_cache_key(settings: Dict[str, Any]) -> str:
+    """
+    Identify the engine for cache entries, so engines don't share evaluations.
+
+    Args:
+        settings: Settings dictionary with the engine profile applied
+
+    Returns:
+        Short hex digest of the engine path and its extra UCI options
+    """
+    engine = {"path": settings["stockfish_path"], "options": settings["engine_options"]}
+    return hashlib.sha1(json.dumps(engine, sort_keys=True).encode()).hexdigest()[:16]
+
+
+def game_position_fens(pgn_text: str) -> List[str]:
+    """
+    Get the normalized FEN of every position in a game, including the final one.
+
+    Args:
+        pgn_text: Game in PGN format
+
+    Returns:
+        List of normalized FENs in move order
+    """
+    game = chess.pgn.read_game(io.StringIO(pgn_text))
+    if not game:
+        return []
+
+    board = game.board()
+    fens = [board.epd()]
+    for move in game.mainline_moves():
+        board.push(move)
+        fens.append(board.epd())
+    return fens
+
+
+class PositionCacheStats:
+    """
+    Hit and miss counters since startup.
+    Only updated from the event loop, so no locking is needed.
+    """
+
+    def __init__(self):
+        self.hits = 0
+        self.misses = 0
+
+    def record(self, hit: bool):
+        """
+        Count one cache lookup.
+
+        Args:
+            hit: Whether the position was found in the cache
+        """
+        if hit:
+            self.hits += 1
+        else:
+            self.misses += 1
+
+    def reset(self):
+        """Reset the counters (e.g. after purging the cache)."""
+        self.hits = 0
+        self.misses = 0
+
+
+# Global counters shared by all analysis jobs
+cache_stats = PositionCacheStats()
+
+
+async def load_cached_positions(
+    db: AsyncSession,
+    pgn_text: str,
+    settings: Dict[str, Any]
+) -> Dict[str, Dict[str, Any]]:
+    """
+    Prefetch cached analyses for all positions of a game.
+
+    Args:
+        db: Database session
+        pgn_text: Game in PGN format
+        settings: Settings dictionary from get_stockfish_settings
+
+    Returns:
+        Dictionary mapping normalized FEN to a cached analysis
+    """
+    return await crud_position_cache.get_cached_positions(
+        db,
+        game_position_fens(pgn_text),
+        engine_cache_key(settings),
+        settings["analysis_depth"],
+        settings["analysis_time_ms"],
+        settings["analysis_multipv"]
+    )
+
+
+async def store_game_positions(
+    db: AsyncSession,
+    analysis_result: Dict[str, Any],
+    settings: Dict[str, Any]
+) -> int:
+    """
+    Add the freshly searched positions of an analysed game to the cache.
+
+    Positions that came from the cache, failed, were skipped, or only got
+    the reduced opening book or quick-pass budget are left out.
+
+    Args:
+        db: Database session
+        analysis_result: Result of StockfishAnalyzer.analyze_game
+        settings: Settings dictionary the game was analysed with
+
+    Returns:
+        Number of positions submitted to the cache
+    """
+    analyses = [move["analysis"] for move in analysis_result.get("moves", [])]
+    if analysis_result.get("final_analysis"):
+        analyses.append(analysis_result["final_analysis"])
+
+    new_positions = {}
+    for analysis in analyses:
+        if analysis.get("cached") or analysis.get("book") or analysis.get("quick"):
+            continue
+        if analysis.get("score_type") in ("error", "skipped"):
+            continue
+        # Tablebase results depend on the installed tables, not the search
+        new_positions[normalize_fen(analysis["fen"])] = {
+            key: value for key, value in analysis.items() if key != "tablebase"
+        }
+
+    return await crud_position_cache.store_positions(
+        db,
+        new_positions,
+        engine_cache_key(settings),
+        settings["analysis_depth"],
+        settings["analysis_time_ms"],
+        settings["analysis_multipv"]
+    )
+
+
+async def get_cache_stats(db: AsyncSession) -> Dict[str, Any]:
+    """
+    Get the cache size and hit rate.
+
+    Args:
+        db: Database session
+
+    Returns:
+        Dictionary with entries, hits, misses and hit_rate (percent, None before any lookup)
+    """
+    lookups = cache_stats.hits + cache_stats.misses
+
+    return {
+        "entries": await crud_position_cache.count_positions(db),
+        "hits": cache_stats.hits,
+        "misses": cache_stats.misses,
+        "hit_rate": round(100 * cache_stats.hits / lookups, 1) if lookups else None
+    }
+
+
+async def purge_cache(db: AsyncSession) -> int:
+    """
+    Delete every cached position and reset the counters.
+
+    Args:
+        db: Database session
+
+    Returns:
+        Number of deleted entries
+    """
+    deleted = await crud_position_cache.purge_positions(db)
+    cache_stats.reset()
+    return deleted
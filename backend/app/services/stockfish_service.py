@@ -1,8 +1,9 @@
-import subprocess
 import json
 import os
 import asyncio
-from typing import Dict, List, Optional, Any
+import contextlib
+import time
+from typing import Dict, List, Optional, Any, Callable
 from pathlib import Path
 import chess
 import chess.pgn
@@ -10,6 +11,58 @@ import io
 from sqlalchemy.ext.asyncio import AsyncSession
 from sqlalchemy import select
 from ..db.models import Setting
+from ..crud import games as crud_games
+from ..crud import analyses as crud_analyses
+from ..crud import analysis_jobs as crud_jobs
+from .move_classification import classify_moves, DEFAULT_THRESHOLDS
+from .accuracy import annotate_win_probabilities, compute_player_stats
+from .position_cache import normalize_fen, cache_stats, load_cached_positions, store_game_positions
+from .openings import OpeningBook
+from .tablebase import TablebaseProber
+from .uci_client import UciClient
+from .analysis_staleness import settings_fingerprint
+from .engine_profiles import resolve_engine_profile
+from .analysis_presets import TIME_CLASSES, resolve_analysis_options
+from .analysis_scope import (
+    AnalysisScope,
+    resolve_scope,
+    skipped_move_entry,
+    find_critical_plies,
+    find_adaptive_plies
+)
+
+
+# Seconds a search may overrun its movetime before the engine is told to stop
+SEARCH_TIMEOUT_MARGIN = 10.0
+
+# Limits of the "go" command per analysis_limit_mode setting. Only "nodes" and
+# "depth" give the same result on every machine; "mate" also stops at movetime.
+LIMIT_MODES = {
+    "depth_time": ("depth", "movetime"),
+    "depth": ("depth",),
+    "movetime": ("movetime",),
+    "nodes": ("nodes",),
+    "mate": ("mate", "movetime")
+}
+
+# analysis_settings field of each "go" limit
+LIMIT_FIELDS = {"depth": "depth", "movetime": "time_ms", "nodes": "nodes", "mate": "mate_moves"}
+
+# Search statistics that depend on machine speed, left out in deterministic mode
+TIMING_STATS = ("time_ms", "nps")
+
+# Search speed assumed when deterministic analyses charge their time budget,
+# so the same nodes cost the same time on every machine
+DETERMINISTIC_NODES_PER_MS = 1000
+
+# Options a single analysis job may override, and the setting each replaces
+SETTING_OVERRIDES = {
+    "depth": "analysis_depth",
+    "time_ms": "analysis_time_ms",
+    "multipv": "analysis_multipv",
+    "threads": "stockfish_threads",
+    "hash_mb": "stockfish_hash"
+}
 
 
 async def get_stockfish_settings(db: AsyncSession) -> Dict[str, Any]:
@@ -28,7 +81,34 @@ async def get_stockfish_settings(db: AsyncSession) -> Dict[str, Any]:
         "stockfish_threads": "4",
         "stockfish_hash": "512",
         "analysis_depth": "20",
-        "analysis_time_ms": "1000"
+        "analysis_time_ms": "1000",
+        "analysis_multipv": "3",
+        "analysis_position_retries": "2",
+        "analysis_search_timeout_s": "300",
+        "position_cache_enabled": "true",
+        "book_enabled": "true",
+        "book_max_ply": "20",
+        "book_polyglot_path": "",
+        "book_search_depth": "0",
+        "syzygy_path": "",
+        "syzygy_probe_limit": "7",
+        "classification_best_cp": str(DEFAULT_THRESHOLDS["best"]),
+        "classification_inaccuracy_cp": str(DEFAULT_THRESHOLDS["inaccuracy"]),
+        "classification_mistake_cp": str(DEFAULT_THRESHOLDS["mistake"]),
+        "classification_blunder_cp": str(DEFAULT_THRESHOLDS["blunder"]),
+        "critical_pass_depth": "10",
+        "critical_swing_cp": "100",
+        "analysis_limit_mode": "depth_time",
+        "analysis_nodes": "1000000",
+        "analysis_mate_moves": "5",
+        "deterministic_mode": "false",
+        "default_engine": "",
+        "adaptive_analysis_enabled": "false",
+        "adaptive_time_budget_s": "120",
+        "preset_bullet": "quick",
+        "preset_blitz": "",
+        "preset_rapid": "",
+        "preset_classical": "deep"
     }
 
     # Fetch settings from database
@@ -44,14 +124,113 @@ async def get_stockfish_settings(db: AsyncSession) -> Dict[str, Any]:
     final_settings = {
         "stockfish_path": settings.get("stockfish_path", defaults["stockfish_path"]),
         "stockfish_threads": int(settings.get("stockfish_threads", defaults["stockfish_threads"])),
-        "stockfish_hash": int(settings.get("stockfish_hash", defaults["stockfish_hash"])),
+        "stockfish_hash": int(settings.get("stockfish_hash_mb") or settings.get("stockfish_hash") or defaults["stockfish_hash"]),
         "analysis_depth": int(settings.get("analysis_depth", defaults["analysis_depth"])),
-        "analysis_time_ms": int(settings.get("analysis_time_ms", defaults["analysis_time_ms"]))
+        "analysis_time_ms": int(settings.get("analysis_time_ms", defaults["analysis_time_ms"])),
+        "analysis_multipv": int(settings.get("analysis_multipv") or defaults["analysis_multipv"]),
+        "analysis_position_retries": int(settings.get("analysis_position_retries") or defaults["analysis_position_retries"]),
+        "analysis_search_timeout_s": int(settings.get("analysis_search_timeout_s") or defaults["analysis_search_timeout_s"]),
+        "position_cache_enabled": (settings.get("position_cache_enabled") or defaults["position_cache_enabled"]).lower() == "true",
+        "book_enabled": (settings.get("book_enabled") or defaults["book_enabled"]).lower() == "true",
+        "book_max_ply": int(settings.get("book_max_ply") or defaults["book_max_ply"]),
+        "book_polyglot_path": settings.get("book_polyglot_path") or defaults["book_polyglot_path"],
+        "book_search_depth": int(settings.get("book_search_depth") or defaults["book_search_depth"]),
+        "syzygy_path": settings.get("syzygy_path") or defaults["syzygy_path"],
+        "syzygy_probe_limit": int(settings.get("syzygy_probe_limit") or defaults["syzygy_probe_limit"]),
+        "critical_pass_depth": int(settings.get("critical_pass_depth") or defaults["critical_pass_depth"]),
+        "critical_swing_cp": int(settings.get("critical_swing_cp") or defaults["critical_swing_cp"]),
+        "analysis_limit_mode": settings.get("analysis_limit_mode") or defaults["analysis_limit_mode"],
+        "analysis_nodes": int(settings.get("analysis_nodes") or defaults["analysis_nodes"]),
+        "analysis_mate_moves": int(settings.get("analysis_mate_moves") or defaults["analysis_mate_moves"]),
+        "deterministic_mode": (settings.get("deterministic_mode") or defaults["deterministic_mode"]).lower() == "true",
+        "adaptive_analysis_enabled": (settings.get("adaptive_analysis_enabled") or defaults["adaptive_analysis_enabled"]).lower() == "true",
+        "adaptive_time_budget_s": int(settings.get("adaptive_time_budget_s") or defaults["adaptive_time_budget_s"]),
+        "chess_com_username": settings.get("chess_com_username") or "",
+        # Empty means stockfish_path is used; see resolve_engine_profile
+        "default_engine": settings.get("default_engine") or defaults["default_engine"],
+        "engine_profile": None,
+        "engine_options": {},
+        "classification_thresholds": {
+            label: int(settings.get(f"classification_{label}_cp") or defaults[f"classification_{label}_cp"])
+            for label in DEFAULT_THRESHOLDS
+        },
+        # Empty means the analysis settings above apply
+        "time_class_presets": {
+            time_class: settings.get(f"preset_{time_class}", defaults[f"preset_{time_class}"])
+            for time_class in TIME_CLASSES
+        }
     }
 
     return final_settings
 
 
+def apply_deterministic_mode(settings: Dict[str, Any]) -> Dict[str, Any]:
+    """
+    Apply what deterministic mode implies for the search settings.
+
+    Args:
+        settings: Settings dictionary from get_stockfish_settings
+
+    Returns:
+        New settings dictionary; with deterministic_mode set, one thread and the node limit
+    """
+    settings = dict(settings)
+    if settings["deterministic_mode"]:
+        # Multi-threaded and time-limited searches differ from run to run
+        settings["stockfish_threads"] = 1
+        settings["analysis_limit_mode"] = "nodes"
+    return settings
+
+
+def search_limit(limit_mode: str, depth: int, time_ms: int, nodes: int, mate_moves: int) -> Dict[str, Any]:
+    """
+    Describe the limits a search actually uses in a limit mode.
+
+    Args:
+        limit_mode: Key of LIMIT_MODES
+        depth: Configured search depth
+        time_ms: Configured time per move in milliseconds
+        nodes: Configured node limit
+        mate_moves: Configured mate distance
+
+    Returns:
+        Dictionary with "mode" and only the values of the limits in use,
+        e.g. {"mode": "nodes", "nodes": 1000000}
+    """
+    values = {"depth": depth, "movetime": time_ms, "nodes": nodes, "mate": mate_moves}
+    return {"mode": limit_mode, **{LIMIT_FIELDS[limit]: values[limit] for limit in LIMIT_MODES[limit_mode]}}
+
+
+def apply_setting_overrides(settings: Dict[str, Any], overrides: Optional[Dict[str, int]]) -> Dict[str, Any]:
+    """
+    Replace settings with the overrides of a single analysis job.
+
+    Args:
+        settings: Settings dictionary from get_stockfish_settings
+        overrides: Values keyed by SETTING_OVERRIDES keys (unknown keys are ignored)
+
+    Returns:
+        New settings dictionary
+    """
+    settings = dict(settings)
+    for option, value in (overrides or {}).items():
+        if option in SETTING_OVERRIDES and value is not None:
+            settings[SETTING_OVERRIDES[option]] = int(value)
+    return settings
+
+
+class AnalysisCancelled(Exception):
+    """Raised when a game analysis is cancelled before it finished."""
+
+    def __init__(self, analysed_moves: int = 0):
+        """
+        Args:
+            analysed_moves: Number of plies analysed before the cancellation
+        """
+        super().__init__("Analysis cancelled")
+        self.analysed_moves = analysed_moves
+
+
 class StockfishAnalyzer:
     """
     Service for analyzing chess games using Stockfish engine.
@@ -64,179 +243,299 @@ class StockfishAnalyzer:
         threads: int = 4,
         hash_mb: int = 512,
         depth: int = 20,
-        time_ms: int = 1000
+        time_ms: int = 1000,
+        multipv: int = 3,
+        classification_thresholds: Optional[Dict[str, int]] = None,
+        position_retries: int = 2,
+        syzygy_path: str = "",
+        syzygy_probe_limit: int = 7,
+        uci_options: Optional[Dict[str, Any]] = None
     ):
         """
         Initialize Stockfish analyzer.
 
+        Other UCI engines work too; options they don't support are not sent.
+
         Args:
             stockfish_path: Path to Stockfish binary
             threads: Number of CPU threads to use
             hash_mb: Hash table size in MB
             depth: Search depth
             time_ms: Time per move in milliseconds
+            multipv: Number of candidate lines to keep per position
+            classification_thresholds: Centipawn-loss thresholds for move classification
+            position_retries: How often a failed position is retried after restarting the engine
+            syzygy_path: Syzygy tablebase directories (empty to disable)
+            syzygy_probe_limit: Maximum number of pieces for tablebase probing
+            uci_options: Further UCI options of the engine profile, set after the standard ones
         """
         self.stockfish_path = stockfish_path
         self.threads = threads
         self.hash_mb = hash_mb
         self.depth = depth
         self.time_ms = time_ms
-        self.process: Optional[subprocess.Popen] = None
-
-    def start_engine(self):
+        self.multipv = multipv
+        self.classification_thresholds = classification_thresholds or dict(DEFAULT_THRESHOLDS)
+        self.position_retries = position_retries
+        self.syzygy_path = syzygy_path
+        self.syzygy_probe_limit = syzygy_probe_limit
+        self.uci_options = uci_options or {}
+        self.limit_mode = "depth_time"
+        self.nodes = 1000000
+        self.mate_moves = 5
+        self.deterministic = False
+        self.search_timeout_s = 300
+        self.searched_nodes = 0
+        self.client: Optional[UciClient] = None
+        self.cancelled = False
+
+    async def start_engine(self):
         """Start the Stockfish engine process."""
-        if self.process is not None:
+        if self.client is not None:
             return  # Already running
 
+        client = UciClient(self.stockfish_path)
         try:
-            self.process = subprocess.Popen(
-                [self.stockfish_path],
-                stdin=subprocess.PIPE,
-                stdout=subprocess.PIPE,
-                stderr=subprocess.PIPE,
-                text=True,
-                bufsize=1
-            )
-
-            # Initialize UCI
-            self._send_command("uci")
-            self._wait_for_response("uciok")
-
-            # Configure engine
-            self._send_command(f"setoption name Threads value {self.threads}")
-            self._send_command(f"setoption name Hash value {self.hash_mb}")
-            self._send_command("isready")
-            self._wait_for_response("readyok")
+            await client.start()
+
+            # Configure engine (not every engine has a hash table or tablebase support)
+            standard_options = {
+                "Threads": self.threads,
+                "Hash": self.hash_mb,
+                "MultiPV": self.multipv,
+                "UCI_ShowWDL": True
+            }
+            if self.syzygy_path:
+                standard_options["SyzygyPath"] = self.syzygy_path
+                standard_options["SyzygyProbeLimit"] = self.syzygy_probe_limit
+            # UCI option names are case-insensitive
+            supported = {option.lower() for option in client.options}
+            for name, value in {**standard_options, **self.uci_options}.items():
+                if name.lower() in supported:
+                    await client.set_option(name, value)
+            await client.is_ready()
 
         except Exception as e:
+            await client.quit()
             raise RuntimeError(f"Failed to start Stockfish: {str(e)}")
 
-    def stop_engine(self):
+        self.client = client
+
+    async def stop_engine(self):
         """Stop the Stockfish engine process."""
-        if self.process:
+        if self.client:
             try:
-                self._send_command("quit")
-                self.process.wait(timeout=5)
-            except:
-                self.process.kill()
+                await self.client.quit()
             finally:
-                self.process = None
+                self.client = None
+
+    @property
+    def engine_key(self) -> tuple:
+        """Options that require restarting the engine process when changed."""
+        return (
+            self.stockfish_path,
+            self.threads,
+            self.hash_mb,
+            self.syzygy_path,
+            self.syzygy_probe_limit,
+            tuple(sorted(self.uci_options.items()))
+        )
 
-    def _send_command(self, command: str):
-        """Send a command to Stockfish."""
-        if not self.process or not self.process.stdin:
-            raise RuntimeError("Stockfish process not running")
-        self.process.stdin.write(command + "\n")
-        self.process.stdin.flush()
+    def is_running(self) -> bool:
+        """Check whether the engine process is still alive."""
+        return self.client is not None and self.client.is_running()
 
-    def _read_line(self) -> str:
-        """Read a line from Stockfish output."""
-        if not self.process or not self.process.stdout:
+    @property
+    def engine_name(self) -> Optional[str]:
+        """Name and version the engine reported (e.g. "Stockfish 16.1")."""
+        return self.client.name if self.client else None
+
+    async def configure_search(
+        self,
+        depth: int,
+        time_ms: int,
+        multipv: int,
+        classification_thresholds: Optional[Dict[str, int]] = None,
+        position_retries: int = 2,
+        limit_mode: str = "depth_time",
+        nodes: int = 1000000,
+        mate_moves: int = 5,
+        deterministic: bool = False,
+        search_timeout_s: int = 300
+    ):
+        """
+        Update per-game search settings on a running engine.
+
+        Args:
+            depth: Search depth
+            time_ms: Time per move in milliseconds
+            multipv: Number of candidate lines to keep per position
+            classification_thresholds: Centipawn-loss thresholds for move classification
+            position_retries: How often a failed position is retried after restarting the engine
+            limit_mode: Which limits the "go" command uses (key of LIMIT_MODES)
+            nodes: Node limit for limit mode "nodes"
+            mate_moves: Mate distance in moves for limit mode "mate"
+            deterministic: Clear the hash before every search and leave out
+                timing statistics, so results don't depend on the machine
+            search_timeout_s: Seconds a search without movetime may take
+                before it counts as failed and is retried
+        """
+        self.depth = depth
+        self.time_ms = time_ms
+        self.limit_mode = limit_mode
+        self.nodes = nodes
+        self.mate_moves = mate_moves
+        self.deterministic = deterministic
+        self.search_timeout_s = search_timeout_s
+        self.classification_thresholds = classification_thresholds or dict(DEFAULT_THRESHOLDS)
+        self.position_retries = position_retries
+        self.cancelled = False  # A cancellation only applies to the game it was requested for
+
+        if multipv != self.multipv:
+            self.multipv = multipv
+            if self.client:
+                await self.client.set_option("MultiPV", self.multipv)
+                await self.client.is_ready()
+
+    async def cancel(self):
+        """
+        Stop the game being analysed.
+
+        The running search is ended with "stop" and the analysis raises
+        AnalysisCancelled before the next position, keeping the engine usable.
+        """
+        self.cancelled = True
+        if self.client:
+            await self.client.stop()
+
+    def _check_cancelled(self):
+        if self.cancelled:
+            raise AnalysisCancelled()
+
+    async def new_game(self):
+        """Reset engine state (hash, history) before analysing another game."""
+        if not self.client:
             raise RuntimeError("Stockfish process not running")
-        return self.process.stdout.readline().strip()
+        await self.client.new_game()
 
-    def _wait_for_response(self, expected: str, timeout: int = 30):
-        """Wait for a specific response from Stockfish."""
-        import time
-        start_time = time.time()
-        while time.time() - start_time < timeout:
-            line = self._read_line()
-            if expected in line:
-                return line
-        raise TimeoutError(f"Timeout waiting for '{expected}'")
+    def _go_command(self, depth: Optional[int], time_ms: Optional[int]) -> str:
+        """
+        Build the "go" command for the configured limit mode.
+
+        An explicit depth (shallow book or quick-pass searches) is always
+        added, so those searches stay shallow in every limit mode.
+
+        Args:
+            depth: Search depth overriding the configured depth
+            time_ms: Time per move overriding the configured time
 
-    def analyze_position(self, fen: str) -> Dict[str, Any]:
+        Returns:
+            Command such as "go depth 20 movetime 1000" or "go nodes 1000000"
+        """
+        limits = LIMIT_MODES[self.limit_mode]
+        parts = ["go"]
+        if depth or "depth" in limits:
+            parts.append(f"depth {depth or self.depth}")
+        if "nodes" in limits:
+            parts.append(f"nodes {self.nodes}")
+        if "mate" in limits:
+            parts.append(f"mate {self.mate_moves}")
+        if "movetime" in limits:
+            parts.append(f"movetime {time_ms or self.time_ms}")
+        return " ".join(parts)
+
+    async def analyze_position(
+        self,
+        fen: str,
+        depth: Optional[int] = None,
+        searchmoves: Optional[List[str]] = None,
+        time_ms: Optional[int] = None
+    ) -> Dict[str, Any]:
         """
         Analyze a single position.
 
         Args:
             fen: Position in FEN notation
+            depth: Search depth overriding the configured depth
+            searchmoves: Restrict the search to these moves (UCI format)
+            time_ms: Time per move overriding the configured time
 
         Returns:
             Dictionary with analysis results
         """
         try:
-            # Set position
-            self._send_command(f"position fen {fen}")
-
-            # Start analysis
-            self._send_command(f"go depth {self.depth} movetime {self.time_ms}")
+            if not self.client:
+                raise RuntimeError("Stockfish process not running")
+
+            go_command = self._go_command(depth, time_ms)
+            if searchmoves:
+                go_command += " searchmoves " + " ".join(searchmoves)
+
+            # Results must not depend on what the previous search left in the hash
+            if self.deterministic:
+                await self.client.new_game()
+
+            # The engine is stopped if it overruns its movetime by too much.
+            # Depth, node and mate limits can't be converted into a timeout,
+            # so a search still running at the ceiling is treated as a hung
+            # engine and fails, which restarts the engine and retries
+            if "movetime" in LIMIT_MODES[self.limit_mode]:
+                timeout = (time_ms or self.time_ms) / 1000 + SEARCH_TIMEOUT_MARGIN
+                partial_on_timeout = True
+            else:
+                timeout = self.search_timeout_s
+                partial_on_timeout = False
 
-            # Read analysis output
-            best_move = None
-            score_cp = None
-            score_mate = None
-            pv = []
+            collector, best_move = await self.client.search(
+                fen, go_command, timeout=timeout, partial_on_timeout=partial_on_timeout
+            )
+            # Engines that don't report nodes are charged the node limit
+            self.searched_nodes += collector.search_stats()["nodes"] or self.nodes
 
-            while True:
-                line = self._read_line()
+            # Determine whose turn it is from FEN (to normalize score to White's perspective)
+            # FEN format: "position w/b ..." where w=white to move, b=black to move
+            fen_parts = fen.split()
+            is_black_to_move = len(fen_parts) > 1 and fen_parts[1] == 'b'
 
-                if line.startswith("bestmove"):
-                    parts = line.split()
-                    if len(parts) >= 2:
-                        best_move = parts[1]
-                    break
+            # Build candidate lines ordered by MultiPV rank
+            candidate_lines = []
+            for record in collector.lines():
+                candidate = {
+                    "multipv": record.multipv,
+                    "move": record.pv[0] if record.pv else None,
+                    "depth": record.depth,
+                    "pv": record.pv,
+                    "pv_san": self._pv_to_san(fen, record.pv)
+                }
+                candidate.update(self._normalize_score(record.score_cp, record.score_mate, is_black_to_move))
+                candidate["wdl"] = self._normalize_wdl(record.wdl, is_black_to_move)
+                if not record.is_exact:
+                    candidate["bound"] = record.bound
+                candidate_lines.append(candidate)
 
-                if "score cp" in line:
-                    # Extract centipawn score
-                    parts = line.split()
-                    try:
-                        cp_index = parts.index("cp") + 1
-                        score_cp = int(parts[cp_index])
-                    except (ValueError, IndexError):
-                        pass
-
-                if "score mate" in line:
-                    # Extract mate score
-                    parts = line.split()
-                    try:
-                        mate_index = parts.index("mate") + 1
-                        score_mate = int(parts[mate_index])
-                    except (ValueError, IndexError):
-                        pass
-
-                if " pv " in line:
-                    # Extract principal variation
-                    parts = line.split()
-                    try:
-                        pv_index = parts.index("pv") + 1
-                        pv = parts[pv_index:]
-                    except (ValueError, IndexError):
-                        pass
+            top_line = candidate_lines[0] if candidate_lines else None
 
             # Build result
             result = {
                 "fen": fen,
                 "best_move": best_move,
-                "pv": pv[:5] if pv else []  # First 5 moves of principal variation
+                "pv": top_line["pv"] if top_line else [],
+                "depth": top_line["depth"] if top_line else None,
+                "lines": candidate_lines,
+                "search_stats": collector.search_stats()
             }
-
-            # Determine whose turn it is from FEN (to normalize score to White's perspective)
-            # FEN format: "position w/b ..." where w=white to move, b=black to move
-            fen_parts = fen.split()
-            is_black_to_move = len(fen_parts) > 1 and fen_parts[1] == 'b'
-
-            # Stockfish returns scores from side-to-move perspective
-            # Normalize to always be from White's perspective:
-            # - If White to move: keep score as-is
-            # - If Black to move: negate score (so positive = white advantage, negative = black advantage)
-            score_multiplier = -1 if is_black_to_move else 1
-
-            # Add score
-            if score_mate is not None:
-                normalized_mate = score_mate * score_multiplier
-                result["score"] = f"M{normalized_mate}"
-                result["score_type"] = "mate"
-                result["score_value"] = normalized_mate
-            elif score_cp is not None:
-                normalized_cp = score_cp * score_multiplier
-                result["score"] = f"{normalized_cp / 100:.2f}"
-                result["score_type"] = "cp"
-                result["score_value"] = normalized_cp
+            if self.deterministic:
+                for stat in TIMING_STATS:
+                    result["search_stats"].pop(stat, None)
+
+            # Add score of the best line
+            if top_line:
+                result["score"] = top_line["score"]
+                result["score_type"] = top_line["score_type"]
+                result["score_value"] = top_line["score_value"]
+                result["wdl"] = top_line["wdl"]
             else:
-                result["score"] = "0.00"
-                result["score_type"] = "cp"
-                result["score_value"] = 0
+                result.update(self._normalize_score(None, None, is_black_to_move))
 
             return result
 
@@ -248,15 +547,376 @@ class StockfishAnalyzer:
                 "score": "0.00",
                 "score_type": "error",
                 "score_value": 0,
-                "pv": []
+                "pv": [],
+                "lines": []
+            }
+
+    @staticmethod
+    def _normalize_score(
+        score_cp: Optional[int],
+        score_mate: Optional[int],
+        is_black_to_move: bool
+    ) -> Dict[str, Any]:
+        """
+        Convert a side-to-move score into White's perspective.
+
+        Stockfish returns scores from the side to move's point of view.
+        If Black is to move the score is negated, so positive always means
+        White is better.
+
+        Args:
+            score_cp: Centipawn score reported by the engine
+            score_mate: Mate distance reported by the engine
+            is_black_to_move: True if Black is to move in the analysed position
+
+        Returns:
+            Dictionary with score, score_type and score_value
+        """
+        score_multiplier = -1 if is_black_to_move else 1
+
+        if score_mate is not None:
+            normalized_mate = score_mate * score_multiplier
+            return {"score": f"M{normalized_mate}", "score_type": "mate", "score_value": normalized_mate}
+        if score_cp is not None:
+            normalized_cp = score_cp * score_multiplier
+            return {"score": f"{normalized_cp / 100:.2f}", "score_type": "cp", "score_value": normalized_cp}
+        return {"score": "0.00", "score_type": "cp", "score_value": 0}
+
+    @staticmethod
+    def _normalize_wdl(wdl: Optional[List[int]], is_black_to_move: bool) -> Optional[Dict[str, int]]:
+        """
+        Convert side-to-move WDL statistics into White's perspective.
+
+        Args:
+            wdl: Win, draw and loss per mille reported by the engine
+            is_black_to_move: True if Black is to move in the analysed position
+
+        Returns:
+            Dictionary with win, draw and loss per mille for White, or None if not reported
+        """
+        if not wdl or len(wdl) != 3:
+            return None
+
+        win, draw, loss = wdl
+        if is_black_to_move:
+            win, loss = loss, win
+        return {"win": win, "draw": draw, "loss": loss}
+
+    @staticmethod
+    def _pv_to_san(fen: str, pv: List[str]) -> List[str]:
+        """
+        Convert a principal variation from UCI to SAN notation.
+
+        Args:
+            fen: Position the variation starts from
+            pv: Moves in UCI format
+
+        Returns:
+            Moves in SAN notation (stops at the first illegal move)
+        """
+        board = chess.Board(fen)
+        san_moves = []
+
+        for uci in pv:
+            try:
+                move = chess.Move.from_uci(uci)
+                if move not in board.legal_moves:
+                    break
+                san_moves.append(board.san(move))
+                board.push(move)
+            except ValueError:
+                break
+
+        return san_moves
+
+    async def analyze_position_with_retry(
+        self,
+        fen: str,
+        searchmoves: Optional[List[str]] = None,
+        depth: Optional[int] = None,
+        time_ms: Optional[int] = None
+    ) -> Dict[str, Any]:
+        """
+        Analyze a position, restarting the engine and retrying on failure.
+
+        Args:
+            fen: Position in FEN notation
+            searchmoves: Restrict the search to these moves (UCI format)
+            depth: Search depth overriding the configured depth
+            time_ms: Time per move overriding the configured time
+
+        Returns:
+            Dictionary with analysis results
+
+        Raises:
+            RuntimeError: If the position still fails after all retries
+        """
+        for attempt in range(self.position_retries + 1):
+            self._check_cancelled()
+            analysis = await self.analyze_position(fen, depth=depth, searchmoves=searchmoves, time_ms=time_ms)
+            if analysis.get("score_type") != "error":
+                return analysis
+
+            print(f"Analysis of position failed (attempt {attempt + 1}): {analysis.get('error')}")
+
+            # A crashed or desynchronised engine won't recover - start a fresh one
+            await self.stop_engine()
+            await self.start_engine()
+
+        raise RuntimeError(
+            f"Position {fen} failed after {self.position_retries + 1} attempts: {analysis.get('error')}"
+        )
+
+    async def analyze_played_move(
+        self,
+        analysis: Dict[str, Any],
+        uci: str,
+        depth: Optional[int] = None,
+        time_ms: Optional[int] = None
+    ) -> Optional[Dict[str, Any]]:
+        """
+        Score the move that was actually played in an analysed position.
+
+        If the move is one of the candidate lines, that line is reused so the
+        played move and the best move come from the same search. Otherwise
+        the position is searched again restricted to the played move.
+
+        Args:
+            analysis: Analysis of the position before the move
+            uci: Played move in UCI format
+            depth: Search depth overriding the configured depth
+            time_ms: Time per move overriding the configured time
+
+        Returns:
+            Dictionary with the played line's score, wdl, depth, pv and pv_san
+            (starting with the played move), or None for unsearched book positions
+        """
+        if analysis.get("score_type") not in ("cp", "mate"):
+            return None
+
+        for line in analysis.get("lines", []):
+            if line.get("move") == uci:
+                return self._played_line(line)
+
+        searched = await self.analyze_position_with_retry(
+            analysis["fen"], searchmoves=[uci], depth=depth, time_ms=time_ms
+        )
+        if not searched.get("lines"):
+            return None
+        return self._played_line(searched["lines"][0])
+
+    @staticmethod
+    def _played_line(line: Dict[str, Any]) -> Dict[str, Any]:
+        return {
+            "move": line.get("move"),
+            "depth": line.get("depth"),
+            "score": line.get("score"),
+            "score_type": line.get("score_type"),
+            "score_value": line.get("score_value"),
+            "wdl": line.get("wdl"),
+            "pv": line.get("pv", []),
+            "pv_san": line.get("pv_san", [])
+        }
+
+    async def analyze_book_position(
+        self,
+        fen: str,
+        search_depth: int,
+        cached_positions: Optional[Dict[str, Dict[str, Any]]] = None
+    ) -> Dict[str, Any]:
+        """
+        Evaluate a position before a book move with a reduced budget.
+
+        A full evaluation from the cache is used when available. Otherwise the
+        position gets a shallow search, or no search at all when search_depth is 0.
+
+        Args:
+            fen: Position in FEN notation
+            search_depth: Depth of the shallow search (0 to skip searching)
+            cached_positions: Cached analyses keyed by normalized FEN
+
+        Returns:
+            Dictionary with analysis results, marked with "book"
+        """
+        cached = cached_positions.get(normalize_fen(fen)) if cached_positions is not None else None
+        if cached is not None:
+            return {**cached, "fen": fen, "lines": cached.get("lines", [])[:self.multipv], "cached": True}
+
+        if search_depth > 0:
+            analysis = await self.analyze_position(fen, depth=search_depth)
+            if analysis.get("score_type") != "error":
+                return {**analysis, "book": True}
+
+        return {
+            "fen": fen,
+            "best_move": None,
+            "pv": [],
+            "depth": 0,
+            "lines": [],
+            "score": None,
+            "score_type": "book",
+            "score_value": None,
+            "book": True
+        }
+
+    async def analyze_position_cached(
+        self,
+        fen: str,
+        cached_positions: Optional[Dict[str, Dict[str, Any]]] = None,
+        quick_depth: Optional[int] = None,
+        quick_time_ms: Optional[int] = None
+    ) -> Dict[str, Any]:
+        """
+        Reuse a cached evaluation of the position, or search it.
+
+        Args:
+            fen: Position in FEN notation
+            cached_positions: Cached analyses keyed by normalized FEN (None disables the cache)
+            quick_depth: Search only this deep when the position is not cached
+            quick_time_ms: Time per move of the quick search (configured time if None)
+
+        Returns:
+            Dictionary with analysis results; cached results have "cached" set,
+            results of a quick search have "quick" set
+        """
+        cached = None
+        if cached_positions is not None:
+            cached = cached_positions.get(normalize_fen(fen))
+            cache_stats.record(cached is not None)
+
+        if cached is None:
+            analysis = await self.analyze_position_with_retry(fen, depth=quick_depth, time_ms=quick_time_ms)
+            if quick_depth is not None:
+                analysis["quick"] = True
+            return analysis
+
+        # The entry may have been searched with more lines than requested
+        return {
+            **cached,
+            "fen": fen,
+            "lines": cached.get("lines", [])[:self.multipv],
+            "cached": True
+        }
+
+    def _search_clock_ms(self) -> float:
+        """
+        Read the clock that time budgets are charged against.
+
+        Deterministic analyses count the searched nodes at
+        DETERMINISTIC_NODES_PER_MS instead of reading the wall clock, so
+        a budget ends at the same ply on every machine.
+
+        Returns:
+            Milliseconds; only differences between two readings are meaningful
+        """
+        if self.deterministic:
+            return self.searched_nodes / DETERMINISTIC_NODES_PER_MS
+        return time.monotonic() * 1000
+
+    async def _search_plies_in_full(
+        self,
+        move_analysis: List[Dict[str, Any]],
+        indexes: List[int],
+        tablebase: Optional[TablebaseProber],
+        progress_callback: Optional[Callable[[Dict[str, Any]], None]],
+        checkpoint_callback: Optional[Callable[[List[Dict[str, Any]]], None]],
+        budget_ms: Optional[float] = None
+    ):
+        """
+        Second pass of a two-pass analysis: search the given plies of the
+        quick pass with the full budget, replacing their entries.
+
+        Each ply is charged the time its searches took on the budget clock
+        (see _search_clock_ms), so the budget holds in every limit mode.
+
+        Args:
+            move_analysis: Move entries of the quick pass (updated in place)
+            indexes: 0-based plies to search, most important first
+            tablebase: Prober for endgame positions
+            progress_callback: Called after each searched ply
+            checkpoint_callback: Called with all move entries after each searched ply
+            budget_ms: Search time after which no ply is started that might
+                not finish in time (None for no limit)
+        """
+        spent_ms = 0.0
+        searched = 0
+
+        for index in indexes:
+            move_data = move_analysis[index]
+            if not move_data["analysis"].get("quick"):
+                # Cached, or already searched in full before an interruption
+                move_data["critical"] = True
+                continue
+
+            if budget_ms is not None:
+                # Expect the average of the plies searched so far
+                cost_ms = spent_ms / searched if searched else 0
+                if spent_ms >= budget_ms or spent_ms + cost_ms > budget_ms:
+                    break
+
+            self._check_cancelled()
+            search_started = self._search_clock_ms()
+            analysis = await self.analyze_position_with_retry(move_data["fen_before"])
+            if tablebase is not None:
+                analysis["tablebase"] = tablebase.probe(chess.Board(move_data["fen_before"]))
+            played = await self.analyze_played_move(analysis, move_data["uci"])
+            self._check_cancelled()
+
+            if budget_ms is not None:
+                spent_ms += self._search_clock_ms() - search_started
+                searched += 1
+
+            move_analysis[index] = {
+                **move_data,
+                "analysis": analysis,
+                "played_move_analysis": played,
+                "critical": True
             }
 
-    def analyze_game(self, pgn_text: str) -> Dict[str, Any]:
+            if checkpoint_callback:
+                checkpoint_callback(move_analysis)
+
+            if progress_callback:
+                progress_callback({
+                    "move_index": index,
+                    "total_moves": len(move_analysis),
+                    "move": move_analysis[index]
+                })
+
+    async def analyze_game(
+        self,
+        pgn_text: str,
+        progress_callback: Optional[Callable[[Dict[str, Any]], None]] = None,
+        resume_moves: Optional[List[Dict[str, Any]]] = None,
+        checkpoint_callback: Optional[Callable[[List[Dict[str, Any]]], None]] = None,
+        cached_positions: Optional[Dict[str, Dict[str, Any]]] = None,
+        opening_book: Optional[OpeningBook] = None,
+        book_search_depth: int = 0,
+        tablebase: Optional[TablebaseProber] = None,
+        scope: Optional[AnalysisScope] = None
+    ) -> Dict[str, Any]:
         """
         Analyze a complete game from PGN.
 
+        Plies outside the scope are kept in the result marked "skipped".
+        In "critical" scope every ply first gets a quick search, then the
+        plies where the evaluation swings are searched again in full and
+        marked "critical". The "adaptive" scope also searches plies with an
+        unclear classification again, most important first, and stops the
+        second pass when the time budget of the scope is used up.
+
         Args:
             pgn_text: Game in PGN format
+            progress_callback: Called after each analysed move with
+                move_index, total_moves and the move's analysis data
+            resume_moves: Move entries from an interrupted analysis; plies
+                already covered are reused instead of searched again
+            checkpoint_callback: Called with all move entries after each newly analysed move
+            cached_positions: Cached analyses keyed by normalized FEN, reused instead of searching
+            opening_book: Book used to detect opening moves, which get a reduced search budget
+            book_search_depth: Search depth for positions before book moves (0 for no search)
+            tablebase: Prober that adds exact WDL/DTZ results to endgame positions
+            scope: Plies to analyse (all plies if None)
 
         Returns:
             Dictionary with complete game analysis
@@ -279,22 +939,81 @@ class StockfishAnalyzer:
             board = game.board()
             move_analysis = []
             move_number = 1
-
-            for move in game.mainline_moves():
+            mainline_moves = list(game.mainline_moves())
+
+            # Only reuse checkpointed plies that match this game's moves
+            resume_moves = resume_moves or []
+            resumable = 0
+            while (
+                resumable < min(len(resume_moves), len(mainline_moves))
+                and resume_moves[resumable].get("uci") == mainline_moves[resumable].uci()
+            ):
+                resumable += 1
+
+            # The book ends at the first move that leaves known theory
+            in_book = opening_book is not None
+
+            scope = scope or AnalysisScope()
+            quick_depth = scope.quick_depth if scope.two_pass else None
+            quick_time_ms = scope.sweep_time_ms(len(mainline_moves) - resumable, self.time_ms) if scope.two_pass else None
+            started = time.monotonic()
+            budget_started = self._search_clock_ms()
+
+            for move in mainline_moves:
                 # Get current position before move
                 fen = board.fen()
 
-                # Analyze position
-                analysis = self.analyze_position(fen)
-
-                # Add move information
-                move_data = {
-                    "move_number": move_number,
-                    "move": board.san(move),  # Move in algebraic notation
-                    "uci": move.uci(),  # Move in UCI format
-                    "fen_before": fen,
-                    "analysis": analysis
-                }
+                if len(move_analysis) < resumable:
+                    # Already analysed before the interruption
+                    move_data = resume_moves[len(move_analysis)]
+                    in_book = in_book and move_data.get("book", False)
+                    board.push(move)
+                    move_analysis.append(move_data)
+                    move_number += 1
+
+                    if progress_callback:
+                        progress_callback({
+                            "move_index": len(move_analysis) - 1,
+                            "total_moves": len(mainline_moves),
+                            "move": move_data
+                        })
+                    continue
+
+                self._check_cancelled()
+                in_book = in_book and opening_book.is_book_move(board, move)
+
+                if not scope.includes(len(move_analysis), board.turn == chess.WHITE):
+                    # Outside the requested scope - no engine search
+                    move_data = skipped_move_entry(board, move, move_number)
+                else:
+                    # Analyze position
+                    if in_book:
+                        analysis = await self.analyze_book_position(fen, book_search_depth, cached_positions)
+                    else:
+                        analysis = await self.analyze_position_cached(fen, cached_positions, quick_depth, quick_time_ms)
+
+                    # Exact endgame results take precedence over the engine's estimate
+                    if tablebase is not None:
+                        analysis["tablebase"] = tablebase.probe(board)
+
+                    # Add move information
+                    move_data = {
+                        "move_number": move_number,
+                        "move": board.san(move),  # Move in algebraic notation
+                        "uci": move.uci(),  # Move in UCI format
+                        "fen_before": fen,
+                        "analysis": analysis,
+                        "played_move_analysis": (
+                            None if in_book
+                            else await self.analyze_played_move(
+                                analysis, move.uci(), depth=quick_depth, time_ms=quick_time_ms
+                            )
+                        ),
+                        "book": in_book
+                    }
+
+                    # A stopped search is incomplete - don't keep it
+                    self._check_cancelled()
 
                 # Make the move
                 board.push(move)
@@ -303,7 +1022,49 @@ class StockfishAnalyzer:
                 move_analysis.append(move_data)
                 move_number += 1
 
+                if checkpoint_callback:
+                    checkpoint_callback(move_analysis)
+
+                if progress_callback:
+                    progress_callback({
+                        "move_index": len(move_analysis) - 1,
+                        "total_moves": len(mainline_moves),
+                        "move": move_data
+                    })
+
+            # Analyze the final position so the last move can be classified too
+            final_analysis = await self.analyze_position_cached(board.fen(), cached_positions)
+            self._check_cancelled()
+            if tablebase is not None:
+                final_analysis["tablebase"] = tablebase.probe(board)
+
+            if scope.mode == "critical":
+                await self._search_plies_in_full(
+                    move_analysis,
+                    find_critical_plies(move_analysis, final_analysis, scope.swing_cp),
+                    tablebase, progress_callback, checkpoint_callback
+                )
+            elif scope.mode == "adaptive":
+                await self._search_plies_in_full(
+                    move_analysis,
+                    find_adaptive_plies(move_analysis, final_analysis, scope.swing_cp, self.classification_thresholds),
+                    tablebase, progress_callback, checkpoint_callback,
+                    budget_ms=scope.full_search_budget_ms(self._search_clock_ms() - budget_started)
+                )
+
+            # Classify every move from consecutive evaluations
+            classification_summary = classify_moves(
+                move_analysis,
+                final_analysis,
+                self.classification_thresholds
+            )
+
+            # Convert evaluations to win probabilities and score each player
+            annotate_win_probabilities(move_analysis, final_analysis)
+            player_stats = compute_player_stats(move_analysis)
+
             # Build complete analysis
+            limit = search_limit(self.limit_mode, self.depth, self.time_ms, self.nodes, self.mate_moves)
             analysis_result = {
                 "game_info": {
                     "white": white_player,
@@ -312,18 +1073,45 @@ class StockfishAnalyzer:
                     "date": date
                 },
                 "analysis_settings": {
-                    "depth": self.depth,
-                    "time_ms": self.time_ms,
+                    # Only the limits the searches used; e.g. no depth in limit mode "nodes"
+                    "depth": limit.get("depth"),
+                    "time_ms": limit.get("time_ms"),
                     "threads": self.threads,
-                    "hash_mb": self.hash_mb
+                    "hash_mb": self.hash_mb,
+                    "multipv": self.multipv,
+                    "engine_name": self.engine_name,
+                    "engine_path": self.stockfish_path,
+                    "classification_thresholds": self.classification_thresholds,
+                    "book_max_ply": opening_book.max_ply if opening_book else 0,
+                    "book_search_depth": book_search_depth,
+                    "syzygy_probe_limit": tablebase.probe_limit if tablebase else 0,
+                    "scope": scope.to_dict(),
+                    "two_pass": scope.two_pass,
+                    "quick_depth": quick_depth,
+                    "limit": limit,
+                    "deterministic": self.deterministic
                 },
                 "moves": move_analysis,
                 "total_moves": len(move_analysis),
-                "final_fen": board.fen()
+                "final_fen": board.fen(),
+                "final_analysis": final_analysis,
+                "classification_summary": classification_summary,
+                "player_stats": player_stats
             }
 
+            # Engine time of this run (resumed plies not included); it varies
+            # between runs, so deterministic analyses record the node rate
+            # their time budget was charged at instead
+            if self.deterministic:
+                analysis_result["analysis_settings"]["budget_nodes_per_ms"] = DETERMINISTIC_NODES_PER_MS
+            else:
+                analysis_result["analysis_settings"]["search_time_ms"] = round((time.monotonic() - started) * 1000)
+
             return analysis_result
 
+        except AnalysisCancelled as e:
+            e.analysed_moves = len(move_analysis)
+            raise
         except Exception as e:
             raise RuntimeError(f"Failed to analyze game: {str(e)}")
 
@@ -332,96 +1120,360 @@ async def analyze_game_async(
     game_id: int,
     pgn_text: str,
     db: AsyncSession,
-    data_dir: str = "/app/data"
+    data_dir: str = "/app/data",
+    progress_callback: Optional[Callable[[Dict[str, Any]], None]] = None,
+    cancel_event: Optional[asyncio.Event] = None,
+    overrides: Optional[Dict[str, Any]] = None
 ) -> Dict[str, Any]:
     """
-    Asynchronously analyze a game and save results to JSON file.
-    Fetches Stockfish settings from the database.
+    Asynchronously analyze a game and store the results in the database.
+    Fetches Stockfish settings from the database; the requested preset, or
+    else the preset of the game's time class, replaces the search settings.
+    The "scope" option limits which plies are searched (see AnalysisScope),
+    the "engine" option picks an engine profile instead of the default engine.
+
+    When cancel_event is set the engine search is stopped and AnalysisCancelled
+    is raised; the moves analysed so far stay in the checkpoint, so queueing
+    the game again continues where it stopped.
 
     Args:
         game_id: Database ID of the game
         pgn_text: Game in PGN format
-        db: Database session to fetch settings
-        data_dir: Directory to save analysis results
+        db: Database session to fetch settings and store the analysis
+        data_dir: Directory for the checkpoints of interrupted analyses
+        progress_callback: Called on the event loop after each analysed move
+        cancel_event: Set to cancel the analysis
+        overrides: Preset name ("preset"), engine profile ("engine"), scope ("scope",
+            "from_ply", "to_ply") and search settings (keys of SETTING_OVERRIDES)
+            for this analysis only
 
     Returns:
-        Dictionary with analysis results and file path
+        Dictionary with analysis results and the stored analysis ID
     """
-    # Create analysis directory if it doesn't exist
+    # Checkpoints of unfinished analyses are kept on disk
     analysis_dir = Path(data_dir) / "analysis"
     analysis_dir.mkdir(exist_ok=True)
 
-    # Fetch settings from database
-    settings = await get_stockfish_settings(db)
-
-    # Run analysis in thread pool to avoid blocking
-    def run_analysis():
-        analyzer = StockfishAnalyzer(
-            stockfish_path=settings["stockfish_path"],
-            threads=settings["stockfish_threads"],
-            hash_mb=settings["stockfish_hash"],
-            depth=settings["analysis_depth"],
-            time_ms=settings["analysis_time_ms"]
-        )
-
-        try:
-            analyzer.start_engine()
-            result = analyzer.analyze_game(pgn_text)
-            return result
-        finally:
-            analyzer.stop_engine()
-
-    # Run in thread pool
-    loop = asyncio.get_event_loop()
-    analysis_result = await loop.run_in_executor(None, run_analysis)
+    # Fetch settings from database and apply the preset and overrides
+    base_settings = await get_stockfish_settings(db)
+    overrides, preset_name = await resolve_analysis_options(db, pgn_text, overrides, base_settings)
+    settings = apply_setting_overrides(base_settings, overrides)
+    settings, engine_profile = await resolve_engine_profile(db, overrides.get("engine"), settings)
+    settings = apply_deterministic_mode(settings)
+    scope = resolve_scope(overrides, pgn_text, settings)
+    settings["analysis_scope"] = scope.to_dict()
+
+    # Pick up where an interrupted analysis of this game left off
+    partial_path = analysis_dir / f"{game_id}.partial.json"
+    resume_moves = load_analysis_checkpoint(partial_path, settings)
+    if resume_moves:
+        print(f"Resuming analysis of game {game_id} at ply {len(resume_moves) + 1}")
+
+    def save_checkpoint(moves: List[Dict[str, Any]]):
+        save_analysis_checkpoint(partial_path, settings, moves)
+
+    # Opening theory gets a reduced search budget
+    opening_book = None
+    if settings["book_enabled"] and settings["book_max_ply"] > 0:
+        opening_book = OpeningBook(settings["book_max_ply"], settings["book_polyglot_path"])
+
+    # Endgame positions are looked up in the tablebases, if configured
+    tablebase = None
+    if settings["syzygy_path"]:
+        tablebase = TablebaseProber(settings["syzygy_path"], settings["syzygy_probe_limit"])
+
+    # Positions already evaluated in other games are reused instead of searched;
+    # the cache only holds searches limited by depth and time
+    use_cache = settings["position_cache_enabled"] and settings["analysis_limit_mode"] == "depth_time"
+    cached_positions = None
+    if use_cache:
+        cached_positions = await load_cached_positions(db, pgn_text, settings)
+
+    # Borrow a warmed-up engine from the pool
+    from .engine_pool import engine_pool
+    analyzer = await engine_pool.acquire(settings)
+    discard_engine = False
+
+    async def stop_on_cancel():
+        await cancel_event.wait()
+        await analyzer.cancel()
+
+    cancel_watcher = asyncio.create_task(stop_on_cancel()) if cancel_event else None
 
-    # Save to JSON file
-    json_path = analysis_dir / f"{game_id}.json"
-    with open(json_path, 'w') as f:
-        json.dump(analysis_result, f, indent=2)
+    try:
+        # Book and tablebase files are only kept open for the duration of the game
+        with opening_book or contextlib.nullcontext(), tablebase or contextlib.nullcontext():
+            analysis_result = await analyzer.analyze_game(
+                pgn_text,
+                progress_callback=progress_callback,
+                resume_moves=resume_moves,
+                checkpoint_callback=save_checkpoint,
+                cached_positions=cached_positions,
+                opening_book=opening_book,
+                book_search_depth=settings["book_search_depth"],
+                tablebase=tablebase,
+                scope=scope
+            )
+    except AnalysisCancelled:
+        # The search was stopped cleanly - the engine can be reused
+        raise
+    except BaseException:
+        # The engine may be in an unknown state - don't hand it to the next game
+        discard_engine = True
+        raise
+    finally:
+        if cancel_watcher:
+            cancel_watcher.cancel()
+        await engine_pool.release(analyzer, discard=discard_engine)
+
+    # Lets later settings changes find analyses that need redoing
+    analysis_result["analysis_settings"]["fingerprint"] = settings_fingerprint(settings)
+    analysis_result["analysis_settings"]["preset"] = preset_name
+    analysis_result["analysis_settings"]["engine_profile"] = engine_profile
+    analysis_result["analysis_settings"]["engine_options"] = settings["engine_options"]
+    stored = await crud_analyses.save_analysis(db, game_id, analysis_result)
+
+    # The full analysis supersedes the checkpoint
+    partial_path.unlink(missing_ok=True)
+
+    if use_cache:
+        await store_game_positions(db, analysis_result, settings)
 
     return {
         "game_id": game_id,
-        "analysis_file": str(json_path),
+        "analysis_id": stored.id,
         "total_moves": analysis_result.get("total_moves", 0),
+        "player_stats": analysis_result.get("player_stats"),
         "success": True
     }
 
 
-def get_game_analysis(game_id: int, data_dir: str = "/app/data") -> Optional[Dict[str, Any]]:
+def _checkpoint_search_settings(settings: Dict[str, Any]) -> Dict[str, Any]:
+    # Moves analysed with different search settings can't be mixed into one game
+    search_settings = {
+        "engine_path": settings["stockfish_path"],
+        "engine_options": settings["engine_options"],
+        "depth": settings["analysis_depth"],
+        "time_ms": settings["analysis_time_ms"],
+        "multipv": settings["analysis_multipv"],
+        "limit_mode": settings["analysis_limit_mode"],
+        "nodes": settings["analysis_nodes"],
+        "mate_moves": settings["analysis_mate_moves"],
+        "deterministic": settings["deterministic_mode"]
+    }
+    scope = settings.get("analysis_scope")
+    if scope and scope["mode"] != "all":
+        search_settings["scope"] = scope
+    return search_settings
+
+
+def load_analysis_checkpoint(partial_path: Path, settings: Dict[str, Any]) -> List[Dict[str, Any]]:
+    """
+    Load the moves analysed before an interruption.
+
+    Args:
+        partial_path: Path of the game's partial analysis file
+        settings: Current settings from get_stockfish_settings
+
+    Returns:
+        List of move entries, empty if there is no usable checkpoint
+    """
+    if not partial_path.exists():
+        return []
+
+    try:
+        with open(partial_path, 'r') as f:
+            checkpoint = json.load(f)
+    except (OSError, json.JSONDecodeError) as e:
+        print(f"Ignoring unreadable analysis checkpoint {partial_path}: {str(e)}")
+        return []
+
+    if checkpoint.get("search_settings") != _checkpoint_search_settings(settings):
+        return []
+
+    return checkpoint.get("moves", [])
+
+
+def save_analysis_checkpoint(partial_path: Path, settings: Dict[str, Any], moves: List[Dict[str, Any]]):
+    """
+    Write the moves analysed so far to the game's partial analysis file.
+
+    The file is written to a temporary path and then renamed, so a crash
+    mid-write never leaves a truncated checkpoint behind.
+
+    Args:
+        partial_path: Path of the game's partial analysis file
+        settings: Current settings from get_stockfish_settings
+        moves: Move entries analysed so far
     """
-    Load existing analysis from JSON file.
+    tmp_path = partial_path.with_suffix(".tmp")
+    with open(tmp_path, 'w') as f:
+        json.dump({"search_settings": _checkpoint_search_settings(settings), "moves": moves}, f)
+    os.replace(tmp_path, partial_path)
+
+
+async def get_game_analysis(
+    db: AsyncSession,
+    game_id: int,
+    version: Optional[int] = None
+) -> Optional[Dict[str, Any]]:
+    """
+    Load a stored analysis of a game.
 
     Args:
+        db: Database session
         game_id: Database ID of the game
-        data_dir: Directory where analysis files are stored
+        version: Analysis version (the latest if None)
 
     Returns:
-        Analysis dictionary if file exists, None otherwise
+        Analysis dictionary if found, None otherwise
     """
-    json_path = Path(data_dir) / "analysis" / f"{game_id}.json"
+    analysis = await crud_analyses.get_analysis_for_game(db, game_id, version)
 
-    if not json_path.exists():
+    if not analysis:
         return None
 
-    try:
-        with open(json_path, 'r') as f:
-            return json.load(f)
-    except Exception as e:
-        print(f"Error loading analysis for game {game_id}: {e}")
-        return None
+    positions = await crud_analyses.get_positions(db, analysis.id)
+    return ensure_player_stats(crud_analyses.analysis_to_dict(analysis, positions))
 
 
-def has_game_analysis(game_id: int, data_dir: str = "/app/data") -> bool:
+async def has_game_analysis(db: AsyncSession, game_id: int) -> bool:
     """
-    Check if analysis file exists for a game.
+    Check if a game has a stored analysis.
 
     Args:
+        db: Database session
         game_id: Database ID of the game
-        data_dir: Directory where analysis files are stored
 
     Returns:
-        True if analysis file exists, False otherwise
+        True if the game has been analysed, False otherwise
+    """
+    return game_id in await crud_analyses.get_analysed_game_ids(db, [game_id])
+
+
+def ensure_player_stats(analysis: Dict[str, Any]) -> Dict[str, Any]:
+    """
+    Fill in classifications, win probabilities and player stats for analyses
+    saved before they were computed at analysis time.
+
+    Args:
+        analysis: Analysis dictionary loaded from disk
+
+    Returns:
+        The same analysis dictionary, completed in memory
+    """
+    if "player_stats" in analysis:
+        return analysis
+
+    moves = analysis.get("moves", [])
+    final_analysis = analysis.get("final_analysis")
+
+    if "classification_summary" not in analysis:
+        thresholds = analysis.get("analysis_settings", {}).get("classification_thresholds", DEFAULT_THRESHOLDS)
+        analysis["classification_summary"] = classify_moves(moves, final_analysis, thresholds)
+
+    annotate_win_probabilities(moves, final_analysis)
+    analysis["player_stats"] = compute_player_stats(moves)
+    return analysis
+
+
+async def import_analysis_files(db: AsyncSession, data_dir: str = "/app/data") -> int:
     """
-    json_path = Path(data_dir) / "analysis" / f"{game_id}.json"
-    return json_path.exists()
+    Move analyses saved as data/analysis/{game_id}.json into the database.
+
+    Imported files are renamed to {game_id}.json.imported so they are kept
+    as a backup but not imported again. Files of deleted games are left alone.
+
+    Args:
+        db: Database session
+        data_dir: Directory where analysis files were stored
+
+    Returns:
+        Number of imported analyses
+    """
+    analysis_dir = Path(data_dir) / "analysis"
+    if not analysis_dir.exists():
+        return 0
+
+    imported = 0
+
+    for json_path in sorted(analysis_dir.glob("*.json")):
+        # Checkpoints ({id}.partial.json) are not finished analyses
+        if not json_path.stem.isdigit():
+            continue
+
+        game_id = int(json_path.stem)
+        if not await crud_games.get_game_by_id(db, game_id):
+            continue
+
+        if not await has_game_analysis(db, game_id):
+            try:
+                with open(json_path, 'r') as f:
+                    analysis = ensure_player_stats(json.load(f))
+            except Exception as e:
+                print(f"Skipping unreadable analysis file {json_path}: {e}")
+                continue
+
+            await crud_analyses.save_analysis(db, game_id, analysis)
+            stats = analysis["player_stats"]
+            await crud_games.update_game_accuracy(
+                db, game_id, stats["white"]["accuracy"], stats["black"]["accuracy"]
+            )
+            imported += 1
+
+        json_path.rename(json_path.with_name(json_path.name + ".imported"))
+
+    return imported
+
+
+async def reconcile_analysis_status(db: AsyncSession) -> int:
+    """
+    Mark games as completed whose analysis is stored but whose status says otherwise
+    (e.g. after restoring a backup or from versions that didn't track the status).
+
+    Games with a queued or running job are left alone; they are being
+    re-analysed and the worker moves them on.
+
+    Args:
+        db: Database session
+
+    Returns:
+        Number of games updated
+    """
+    analysed = await crud_analyses.get_analysed_game_ids(db)
+    active = {job.game_id for job in await crud_jobs.get_active_jobs(db)}
+    updated = 0
+
+    for status in ('queued', 'failed', 'cancelled'):
+        for game in await crud_games.get_games_by_analysis_status(db, status):
+            if game.id in analysed and game.id not in active:
+                await crud_games.update_game_analysis_status(db, game.id, "completed", force=True)
+                updated += 1
+
+    return updated
+
+
+async def backfill_game_accuracy(db: AsyncSession) -> int:
+    """
+    Store accuracy for completed games analysed before accuracy was tracked.
+
+    Args:
+        db: Database session
+
+    Returns:
+        Number of games updated
+    """
+    updated = 0
+
+    for game in await crud_games.get_games_missing_accuracy(db):
+        analysis = await get_game_analysis(db, game.id)
+        if not analysis:
+            continue
+
+        stats = analysis["player_stats"]
+        await crud_games.update_game_accuracy(
+            db, game.id, stats["white"]["accuracy"], stats["black"]["accuracy"]
+        )
+        updated += 1
+
+    return updated
@@ -206,4 +206,177 @@ def validate_settings(settings: Dict[str, Any]) -> Tuple[bool, list]:
         except ValueError:
             errors.append("Analysis time must be a valid number")
 
+    # Validate number of parallel analysis workers
+    if "analysis_workers" in settings:
+        try:
+            workers = int(settings["analysis_workers"])
+            logical_cores = psutil.cpu_count(logical=True) or 1
+
+            if workers < 1:
+                errors.append("Analysis workers must be at least 1")
+            elif workers > logical_cores:
+                errors.append(
+                    f"Analysis workers ({workers}) exceeds available CPU cores ({logical_cores})"
+                )
+        except (TypeError, ValueError):
+            errors.append("Analysis workers must be a valid number")
+
+    # Validate number of pooled engines
+    if "engine_pool_size" in settings:
+        try:
+            pool_size = int(settings["engine_pool_size"])
+            if pool_size < 1:
+                errors.append("Engine pool size must be at least 1")
+            elif pool_size > 16:
+                errors.append("Engine pool size should not exceed 16")
+        except (TypeError, ValueError):
+            errors.append("Engine pool size must be a valid number")
+
+    # Validate MultiPV line count
+    if "analysis_multipv" in settings:
+        try:
+            multipv = int(settings["analysis_multipv"])
+            if multipv < 1:
+                errors.append("MultiPV must be at least 1")
+            elif multipv > 10:
+                errors.append("MultiPV should not exceed 10 lines")
+        except (TypeError, ValueError):
+            errors.append("MultiPV must be a valid number")
+
+    # Validate the search limits
+    if "analysis_limit_mode" in settings:
+        from .stockfish_service import LIMIT_MODES
+        if settings["analysis_limit_mode"] not in LIMIT_MODES:
+            errors.append(f"Limit mode must be one of: {', '.join(LIMIT_MODES)}")
+
+    if "analysis_nodes" in settings:
+        try:
+            nodes = int(settings["analysis_nodes"])
+            if nodes < 1000:
+                errors.append("Node limit must be at least 1000")
+            elif nodes > 1000000000:
+                errors.append("Node limit should not exceed 1,000,000,000")
+        except (TypeError, ValueError):
+            errors.append("Node limit must be a valid number")
+
+    if "analysis_mate_moves" in settings:
+        try:
+            mate_moves = int(settings["analysis_mate_moves"])
+            if mate_moves < 1:
+                errors.append("Mate search must look for a mate in at least 1 move")
+            elif mate_moves > 30:
+                errors.append("Mate search should not exceed 30 moves")
+        except (TypeError, ValueError):
+            errors.append("Mate search distance must be a valid number")
+
+    # Validate per-position retry count
+    if "analysis_position_retries" in settings:
+        try:
+            retries = int(settings["analysis_position_retries"])
+            if retries < 0:
+                errors.append("Position retries must not be negative")
+            elif retries > 5:
+                errors.append("Position retries should not exceed 5")
+        except (TypeError, ValueError):
+            errors.append("Position retries must be a valid number")
+
+    if "analysis_search_timeout_s" in settings:
+        try:
+            search_timeout = int(settings["analysis_search_timeout_s"])
+            if search_timeout < 10:
+                errors.append("Search timeout must be at least 10 seconds")
+            elif search_timeout > 3600:
+                errors.append("Search timeout should not exceed 3600 seconds (1 hour)")
+        except (TypeError, ValueError):
+            errors.append("Search timeout must be a valid number")
+
+    # Validate opening book settings
+    if "book_max_ply" in settings:
+        try:
+            max_ply = int(settings["book_max_ply"])
+            if max_ply < 0:
+                errors.append("Book depth must not be negative")
+            elif max_ply > 60:
+                errors.append("Book depth should not exceed 60 plies")
+        except (TypeError, ValueError):
+            errors.append("Book depth must be a valid number")
+
+    if "book_search_depth" in settings:
+        try:
+            book_depth = int(settings["book_search_depth"])
+            if book_depth < 0:
+                errors.append("Book search depth must not be negative")
+            elif book_depth > 30:
+                errors.append("Book search depth should not exceed 30")
+        except (TypeError, ValueError):
+            errors.append("Book search depth must be a valid number")
+
+    # Validate the quick pass of critical-moment analyses
+    if "critical_pass_depth" in settings:
+        try:
+            pass_depth = int(settings["critical_pass_depth"])
+            if pass_depth < 1:
+                errors.append("Critical pass depth must be at least 1")
+            elif pass_depth > 30:
+                errors.append("Critical pass depth should not exceed 30")
+        except (TypeError, ValueError):
+            errors.append("Critical pass depth must be a valid number")
+
+    if "critical_swing_cp" in settings:
+        try:
+            if int(settings["critical_swing_cp"]) < 10:
+                errors.append("Critical swing must be at least 10 centipawns")
+        except (TypeError, ValueError):
+            errors.append("Critical swing must be a valid number")
+
+    # Validate the time budget of adaptive analyses
+    if "adaptive_time_budget_s" in settings:
+        try:
+            budget = int(settings["adaptive_time_budget_s"])
+            if budget < 10:
+                errors.append("Adaptive time budget must be at least 10 seconds")
+            elif budget > 3600:
+                errors.append("Adaptive time budget should not exceed 3600 seconds (1 hour)")
+        except (TypeError, ValueError):
+            errors.append("Adaptive time budget must be a valid number")
+
+    if settings.get("book_polyglot_path"):
+        if not os.path.isfile(settings["book_polyglot_path"]):
+            errors.append(f"Polyglot book not found: {settings['book_polyglot_path']}")
+
+    # Validate Syzygy tablebase settings
+    if settings.get("syzygy_path"):
+        for directory in settings["syzygy_path"].split(os.pathsep):
+            if directory.strip() and not os.path.isdir(directory):
+                errors.append(f"Tablebase directory not found: {directory}")
+
+    if "syzygy_probe_limit" in settings:
+        try:
+            probe_limit = int(settings["syzygy_probe_limit"])
+            if probe_limit < 0 or probe_limit > 7:
+                errors.append("Tablebase probe limit must be between 0 and 7 pieces")
+        except (TypeError, ValueError):
+            errors.append("Tablebase probe limit must be a valid number")
+
+    # Validate move classification thresholds
+    threshold_keys = [
+        "classification_best_cp",
+        "classification_inaccuracy_cp",
+        "classification_mistake_cp",
+        "classification_blunder_cp"
+    ]
+    thresholds = []
+    for key in threshold_keys:
+        if key in settings:
+            try:
+                value = int(settings[key])
+                if value < 0:
+                    errors.append(f"{key} must not be negative")
+                thresholds.append(value)
+            except (TypeError, ValueError):
+                errors.append(f"{key} must be a valid number")
+
+    if len(thresholds) == len(threshold_keys) and not all(a < b for a, b in zip(thresholds, thresholds[1:])):
+        errors.append("Classification thresholds must increase: best < inaccuracy < mistake < blunder")
+
     return len(errors) == 0, errors
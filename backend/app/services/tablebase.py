@@ -0,0 +1,112 @@
+import os
+from typing import Dict, List, Optional, Any
+
+import chess
+import chess.syzygy
+
+
+# Largest Syzygy tables that exist (7-piece)
+MAX_TABLEBASE_PIECES = 7
+
+
+def tablebase_directories(syzygy_path: str) -> List[str]:
+    """
+    Split a SyzygyPath value into its directories.
+
+    Like Stockfish, several directories are separated by ':' (';' on Windows).
+
+    Args:
+        syzygy_path: SyzygyPath setting value
+
+    Returns:
+        List of non-empty directory paths
+    """
+    return [directory for directory in (syzygy_path or "").split(os.pathsep) if directory.strip()]
+
+
+class TablebaseProber:
+    """
+    Looks up exact endgame results in Syzygy tablebases.
+
+    Use as a context manager so the table files are only kept open while
+    a game is analysed.
+    """
+
+    def __init__(self, syzygy_path: str, probe_limit: int = MAX_TABLEBASE_PIECES):
+        """
+        Initialize the prober.
+
+        Args:
+            syzygy_path: Tablebase directories (SyzygyPath format)
+            probe_limit: Only probe positions with at most this many pieces
+        """
+        self.directories = tablebase_directories(syzygy_path)
+        self.probe_limit = min(probe_limit, MAX_TABLEBASE_PIECES)
+        self._tablebase: Optional[chess.syzygy.Tablebase] = None
+
+    def __enter__(self):
+        self._tablebase = chess.syzygy.Tablebase()
+        for directory in self.directories:
+            self._tablebase.add_directory(directory)
+        return self
+
+    def __exit__(self, *exc_info):
+        if self._tablebase is not None:
+            self._tablebase.close()
+            self._tablebase = None
+
+    def probe(self, board: chess.Board) -> Optional[Dict[str, Any]]:
+        """
+        Get the exact result of a position.
+
+        WDL and DTZ are normalized to White's perspective like engine scores:
+        WDL is 2 (White wins), 1 (cursed win), 0 (draw), -1 (blessed loss)
+        or -2 (White loses); DTZ is the distance to a zeroing move.
+
+        Args:
+            board: Position to probe
+
+        Returns:
+            Dictionary with wdl, dtz and pieces, or None if the position
+            is not covered by the available tables
+        """
+        if self._tablebase is None:
+            return None
+
+        pieces = chess.popcount(board.occupied)
+        if pieces > self.probe_limit or board.castling_rights:
+            return None
+
+        try:
+            wdl = self._tablebase.probe_wdl(board)
+            dtz = self._tablebase.probe_dtz(board)
+        except KeyError:
+            return None  # Table for this material is not installed
+
+        sign = 1 if board.turn == chess.WHITE else -1
+        return {"wdl": wdl * sign, "dtz": dtz * sign, "pieces": pieces}
+
+
+def tablebase_outcome(analysis: Optional[Dict[str, Any]]) -> Optional[int]:
+    """
+    Get the game-theoretic outcome of a tablebase-resolved position.
+
+    Cursed wins and blessed losses are drawn under the 50-move rule,
+    so they count as draws.
+
+    Args:
+        analysis: Position analysis with an optional "tablebase" entry
+
+    Returns:
+        1 if White wins, 0 for a draw, -1 if White loses, None if not resolved
+    """
+    tablebase = analysis.get("tablebase") if analysis else None
+    if not tablebase:
+        return None
+
+    wdl = tablebase["wdl"]
+    if wdl == 2:
+        return 1
+    if wdl == -2:
+        return -1
+    return 0
@@ -0,0 +1,317 @@
+import asyncio
+import collections
+from typing import Dict, List, Optional, Tuple
+
+from .uci_parser import SearchCollector, parse_info_line, parse_bestmove_line
+
+
+# Seconds to wait for handshake and synchronisation commands (uci, isready, quit)
+DEFAULT_COMMAND_TIMEOUT = 30.0
+
+# Seconds a search may take after "stop" before the engine is considered hung
+STOP_TIMEOUT = 5.0
+
+# Lines of engine stderr kept for error messages
+STDERR_TAIL_LINES = 50
+
+
+class UciError(RuntimeError):
+    """Raised when the engine misbehaves or can't be talked to."""
+
+
+class UciTimeoutError(UciError):
+    """Raised when the engine doesn't answer a command in time."""
+
+
+class EngineTerminatedError(UciError):
+    """Raised when the engine process exits unexpectedly."""
+
+
+class UciClient:
+    """
+    Asyncio-native connection to a UCI engine process.
+
+    Every read has a timeout, so a hung engine raises UciTimeoutError instead
+    of blocking forever. A running search can be stopped from another task,
+    and a cancelled search is stopped before the cancellation propagates so
+    the engine can be reused. The last lines of stderr are kept for error reports.
+    """
+
+    def __init__(self, path: str):
+        """
+        Initialize the client. The process is started by start().
+
+        Args:
+            path: Path of the engine binary
+        """
+        self.path = path
+        self.name: Optional[str] = None
+        self.author: Optional[str] = None
+        self.options: List[str] = []
+        self.process: Optional[asyncio.subprocess.Process] = None
+        self.searching = False
+        self._stderr_lines = collections.deque(maxlen=STDERR_TAIL_LINES)
+        self._stderr_task: Optional[asyncio.Task] = None
+
+    async def start(self, timeout: float = DEFAULT_COMMAND_TIMEOUT):
+        """
+        Start the engine process and complete the UCI handshake.
+
+        Args:
+            timeout: Seconds to wait for "uciok"
+
+        Raises:
+            OSError: If the binary can't be executed
+            UciError: If the handshake fails
+        """
+        self.process = await asyncio.create_subprocess_exec(
+            self.path,
+            stdin=asyncio.subprocess.PIPE,
+            stdout=asyncio.subprocess.PIPE,
+            stderr=asyncio.subprocess.PIPE
+        )
+        self._stderr_task = asyncio.create_task(self._capture_stderr())
+
+        await self.send("uci")
+        for line in await self.read_until("uciok", timeout):
+            if line.startswith("id name "):
+                self.name = line[len("id name "):]
+            elif line.startswith("id author "):
+                self.author = line[len("id author "):]
+            elif line.startswith("option name "):
+                self.options.append(line[len("option name "):].split(" type ")[0])
+
+    async def _capture_stderr(self):
+        while True:
+            line = await self.process.stderr.readline()
+            if not line:
+                return
+            self._stderr_lines.append(line.decode(errors="replace").rstrip())
+
+    @property
+    def stderr_tail(self) -> str:
+        """The last lines the engine wrote to stderr."""
+        return "\n".join(self._stderr_lines)
+
+    def is_running(self) -> bool:
+        """Check whether the engine process is still alive."""
+        return self.process is not None and self.process.returncode is None
+
+    def _terminated_error(self) -> EngineTerminatedError:
+        message = "Engine process terminated unexpectedly"
+        if self.process is not None and self.process.returncode is not None:
+            message += f" (exit code {self.process.returncode})"
+        if self._stderr_lines:
+            message += f": {self.stderr_tail}"
+        return EngineTerminatedError(message)
+
+    async def send(self, command: str):
+        """
+        Send a command to the engine.
+
+        Args:
+            command: UCI command without trailing newline
+        """
+        if not self.is_running():
+            raise self._terminated_error()
+
+        try:
+            self.process.stdin.write((command + "\n").encode())
+            await self.process.stdin.drain()
+        except (BrokenPipeError, ConnectionResetError):
+            raise self._terminated_error()
+
+    async def read_line(self, timeout: Optional[float] = DEFAULT_COMMAND_TIMEOUT) -> str:
+        """
+        Read one line of engine output.
+
+        Args:
+            timeout: Seconds to wait (None waits indefinitely)
+
+        Returns:
+            The line without surrounding whitespace
+
+        Raises:
+            UciTimeoutError: If no line arrives in time
+            EngineTerminatedError: If the engine closed its output
+        """
+        if self.process is None:
+            raise self._terminated_error()
+
+        try:
+            line = await asyncio.wait_for(self.process.stdout.readline(), timeout)
+        except asyncio.TimeoutError:
+            raise UciTimeoutError(f"Engine did not respond within {timeout} seconds")
+
+        if not line:
+            # readline() only returns an empty line at EOF
+            await self._wait_for_exit()
+            raise self._terminated_error()
+        return line.decode(errors="replace").strip()
+
+    async def _wait_for_exit(self):
+        # Give the process a moment to exit so its code and stderr are available
+        try:
+            await asyncio.wait_for(self.process.wait(), 1.0)
+            if self._stderr_task is not None:
+                await asyncio.wait_for(asyncio.shield(self._stderr_task), 1.0)
+        except asyncio.TimeoutError:
+            pass
+
+    async def read_until(self, expected: str, timeout: float = DEFAULT_COMMAND_TIMEOUT) -> List[str]:
+        """
+        Read lines until one starts with the expected token.
+
+        Args:
+            expected: Token that ends the response (e.g. "readyok")
+            timeout: Seconds to wait for the whole response
+
+        Returns:
+            All lines read, including the final one
+        """
+        loop = asyncio.get_running_loop()
+        deadline = loop.time() + timeout
+        lines = []
+
+        while True:
+            remaining = deadline - loop.time()
+            if remaining <= 0:
+                raise UciTimeoutError(f"Timeout waiting for '{expected}'")
+            line = await self.read_line(remaining)
+            lines.append(line)
+            if line.startswith(expected):
+                return lines
+
+    async def set_option(self, name: str, value):
+        """
+        Set an engine option.
+
+        Args:
+            name: Option name
+            value: Option value (booleans are sent as true/false)
+        """
+        if isinstance(value, bool):
+            value = "true" if value else "false"
+        await self.send(f"setoption name {name} value {value}")
+
+    async def is_ready(self, timeout: float = DEFAULT_COMMAND_TIMEOUT):
+        """Wait until the engine has processed all previous commands."""
+        await self.send("isready")
+        await self.read_until("readyok", timeout)
+
+    async def new_game(self, timeout: float = DEFAULT_COMMAND_TIMEOUT):
+        """Reset the engine's hash and history before a new game."""
+        await self.send("ucinewgame")
+        await self.is_ready(timeout)
+
+    async def search(
+        self,
+        fen: str,
+        go_command: str,
+        timeout: Optional[float] = None,
+        partial_on_timeout: bool = True
+    ) -> Tuple[SearchCollector, Optional[str]]:
+        """
+        Search a position and collect the engine output.
+
+        When the timeout expires the engine is told to stop and its result so
+        far is returned. If the task is cancelled the search is stopped before
+        the cancellation is re-raised.
+
+        Args:
+            fen: Position in FEN notation
+            go_command: Full "go" command (e.g. "go depth 20 movetime 1000")
+            timeout: Seconds before the search is stopped (None for no limit)
+            partial_on_timeout: Return the result so far when the timeout
+                expires; otherwise UciTimeoutError is raised after stopping
+
+        Returns:
+            Tuple of the collected info records and the best move (None if there is no legal move)
+
+        Raises:
+            UciTimeoutError: If the timeout expires and partial_on_timeout is False
+        """
+        collector = SearchCollector()
+
+        await self.send(f"position fen {fen}")
+        await self.send(go_command)
+        self.searching = True
+
+        try:
+            try:
+                best_move = await asyncio.wait_for(self._collect(collector), timeout)
+            except asyncio.TimeoutError:
+                best_move = await self._stop_and_collect(collector)
+                if not partial_on_timeout:
+                    raise UciTimeoutError(f"Search did not finish within {timeout:g} seconds")
+        except asyncio.CancelledError:
+            try:
+                await self._stop_and_collect(collector)
+            except Exception:
+                pass  # The engine is discarded by the caller anyway
+            raise
+        finally:
+            self.searching = False
+
+        return collector, best_move
+
+    async def _collect(self, collector: SearchCollector) -> Optional[str]:
+        while True:
+            line = await self.read_line(None)
+
+            bestmove = parse_bestmove_line(line)
+            if bestmove is not None:
+                return bestmove[0]
+
+            record = parse_info_line(line)
+            if record is not None:
+                collector.add(record)
+
+    async def _stop_and_collect(self, collector: SearchCollector) -> Optional[str]:
+        await self.send("stop")
+        try:
+            return await asyncio.wait_for(self._collect(collector), STOP_TIMEOUT)
+        except asyncio.TimeoutError:
+            raise UciTimeoutError(f"Engine did not stop searching within {STOP_TIMEOUT} seconds")
+
+    async def stop(self):
+        """
+        Ask a running search to finish early.
+
+        The search returns normally with the results found so far.
+        """
+        if self.searching and self.is_running():
+            await self.send("stop")
+
+    async def quit(self, timeout: float = 5.0):
+        """
+        Shut the engine down, killing it if it doesn't exit in time.
+
+        Args:
+            timeout: Seconds to wait for the process to exit after "quit"
+        """
+        if self.process is None:
+            return
+
+        try:
+            if self.is_running():
+                await self.send("quit")
+                await asyncio.wait_for(self.process.wait(), timeout)
+        except Exception:
+            if self.is_running():
+                self.process.kill()
+                await self.process.wait()
+        finally:
+            if self._stderr_task is not None:
+                self._stderr_task.cancel()
+                self._stderr_task = None
+            self.process = None
+
+    def info(self) -> Dict[str, Optional[str]]:
+        """
+        Get the identity the engine reported during the handshake.
+
+        Returns:
+            Dictionary with path, name and author
+        """
+        return {"path": self.path, "name": self.name, "author": self.author}
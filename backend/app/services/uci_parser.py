@@ -0,0 +1,199 @@
+from dataclasses import dataclass, field
+from typing import Dict, List, Optional, Any, Tuple
+
+
+# "info" tokens followed by a single integer value
+INTEGER_FIELDS = {
+    "depth": "depth",
+    "seldepth": "seldepth",
+    "multipv": "multipv",
+    "nodes": "nodes",
+    "nps": "nps",
+    "hashfull": "hashfull",
+    "tbhits": "tbhits",
+    "time": "time_ms",
+    "currmovenumber": "currmovenumber",
+}
+
+
+@dataclass
+class InfoRecord:
+    """
+    One parsed UCI "info" line.
+
+    Scores are from the side to move's point of view, as sent by the engine.
+    Fields the engine did not send are None.
+    """
+
+    depth: Optional[int] = None
+    seldepth: Optional[int] = None
+    multipv: int = 1
+    score_cp: Optional[int] = None
+    score_mate: Optional[int] = None
+    bound: str = "exact"  # "exact", "lowerbound" or "upperbound"
+    wdl: Optional[List[int]] = None
+    nodes: Optional[int] = None
+    nps: Optional[int] = None
+    hashfull: Optional[int] = None
+    tbhits: Optional[int] = None
+    time_ms: Optional[int] = None
+    currmove: Optional[str] = None
+    currmovenumber: Optional[int] = None
+    string: Optional[str] = None
+    pv: List[str] = field(default_factory=list)
+
+    @property
+    def has_score(self) -> bool:
+        """Whether the record carries a centipawn or mate score."""
+        return self.score_cp is not None or self.score_mate is not None
+
+    @property
+    def is_exact(self) -> bool:
+        """Whether the score is exact rather than a fail-high/fail-low bound."""
+        return self.bound == "exact"
+
+
+def parse_info_line(line: str) -> Optional[InfoRecord]:
+    """
+    Parse a UCI "info" line into a record.
+
+    Unknown tokens are skipped; "pv" and "string" consume the rest of the line.
+
+    Args:
+        line: Raw line from the engine
+
+    Returns:
+        InfoRecord, or None if the line is not an info line
+    """
+    tokens = line.split()
+    if not tokens or tokens[0] != "info":
+        return None
+
+    record = InfoRecord()
+    index = 1
+
+    while index < len(tokens):
+        token = tokens[index]
+
+        try:
+            if token in INTEGER_FIELDS:
+                setattr(record, INTEGER_FIELDS[token], int(tokens[index + 1]))
+                index += 2
+            elif token == "score":
+                index += 1
+                while index < len(tokens) and tokens[index] in ("cp", "mate", "lowerbound", "upperbound"):
+                    kind = tokens[index]
+                    if kind == "cp":
+                        record.score_cp = int(tokens[index + 1])
+                        index += 2
+                    elif kind == "mate":
+                        record.score_mate = int(tokens[index + 1])
+                        index += 2
+                    else:
+                        record.bound = kind
+                        index += 1
+            elif token == "wdl":
+                record.wdl = [int(value) for value in tokens[index + 1:index + 4]]
+                index += 4
+            elif token == "currmove":
+                record.currmove = tokens[index + 1]
+                index += 2
+            elif token == "pv":
+                record.pv = tokens[index + 1:]
+                break
+            elif token == "string":
+                record.string = " ".join(tokens[index + 1:])
+                break
+            else:
+                index += 1
+        except (ValueError, IndexError):
+            # Malformed value - skip the token and keep what was parsed so far
+            index += 1
+
+    return record
+
+
+def parse_bestmove_line(line: str) -> Optional[Tuple[Optional[str], Optional[str]]]:
+    """
+    Parse a UCI "bestmove" line.
+
+    Args:
+        line: Raw line from the engine
+
+    Returns:
+        Tuple of (best move, ponder move), or None if the line is not a bestmove line.
+        The best move is None when the engine reports "(none)" (no legal moves).
+    """
+    tokens = line.split()
+    if not tokens or tokens[0] != "bestmove":
+        return None
+
+    best_move = tokens[1] if len(tokens) > 1 and tokens[1] != "(none)" else None
+    ponder = tokens[3] if len(tokens) > 3 and tokens[2] == "ponder" else None
+    return best_move, ponder
+
+
+class SearchCollector:
+    """
+    Collects info records of one search and selects the final result.
+
+    For every MultiPV line the deepest exact-bound record is kept, preferring
+    records with a PV; fail-high/fail-low records are only used for a line
+    that never received an exact score (e.g. when the time ran out during a
+    re-search). Records without a PV still count, since engines report
+    mated or stalemated positions as e.g. "info depth 0 score mate 0".
+    """
+
+    def __init__(self):
+        self.exact: Dict[int, InfoRecord] = {}
+        self.bounded: Dict[int, InfoRecord] = {}
+        self.last_stats: Optional[InfoRecord] = None
+
+    def add(self, record: InfoRecord):
+        """
+        Feed a parsed info record.
+
+        Args:
+            record: Record from parse_info_line
+        """
+        if record.nodes is not None:
+            self.last_stats = record
+
+        if not record.has_score:
+            return
+
+        target = self.exact if record.is_exact else self.bounded
+        previous = target.get(record.multipv)
+        if previous is None or (record.pv and not previous.pv):
+            target[record.multipv] = record
+        elif bool(record.pv) == bool(previous.pv) and (record.depth or 0) >= (previous.depth or 0):
+            target[record.multipv] = record
+
+    def lines(self) -> List[InfoRecord]:
+        """
+        Get the final record of every MultiPV line.
+
+        Returns:
+            Records ordered by MultiPV rank
+        """
+        ranks = sorted(set(self.exact) | set(self.bounded))
+        return [self.exact.get(rank) or self.bounded[rank] for rank in ranks]
+
+    def search_stats(self) -> Dict[str, Any]:
+        """
+        Get statistics of the search from the last record that reported them.
+
+        Returns:
+            Dictionary with depth, seldepth, nodes, nps, hashfull (per mille),
+            tbhits and time_ms; values the engine did not send are None
+        """
+        record = self.last_stats or InfoRecord()
+        return {
+            "depth": record.depth,
+            "seldepth": record.seldepth,
+            "nodes": record.nodes,
+            "nps": record.nps,
+            "hashfull": record.hashfull,
+            "tbhits": record.tbhits,
+            "time_ms": record.time_ms
+        }
@@ -0,0 +1,103 @@
+import pytest
+
+from app.services.accuracy import (
+    win_probability,
+    analysis_win_probability,
+    move_accuracy,
+    annotate_win_probabilities,
+    compute_player_stats
+)
+
+
+WHITE_FEN = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+BLACK_FEN = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1"
+
+
+def _cp(value, **extra):
+    return {"score_type": "cp", "score_value": value, **extra}
+
+
+def test_win_probability_is_symmetric_and_bounded():
+    assert win_probability(0) == pytest.approx(50.0)
+    assert win_probability(100) == pytest.approx(59.1, abs=0.1)
+    assert win_probability(100) + win_probability(-100) == pytest.approx(100.0)
+    assert win_probability(10 ** 6) == win_probability(10000)
+    assert 0 <= win_probability(-10000) < 1
+
+
+def test_analysis_win_probability_prefers_tablebase_then_wdl():
+    assert analysis_win_probability(_cp(300, tablebase={"wdl": 0})) == 50.0
+    assert analysis_win_probability(_cp(-300, tablebase={"wdl": 2})) == 100.0
+    assert analysis_win_probability(_cp(300, wdl={"win": 0, "draw": 1000, "loss": 0})) == 50.0
+    assert analysis_win_probability(_cp(100)) == pytest.approx(win_probability(100))
+    assert analysis_win_probability({"score_type": "skipped", "score_value": None}) is None
+
+
+def test_move_accuracy():
+    assert move_accuracy(60, 60) == 100.0
+    assert move_accuracy(40, 70) == 100.0
+    assert move_accuracy(60, 50) == pytest.approx(63.6, abs=0.1)
+    assert move_accuracy(100, 0) == 0.0
+
+
+def test_annotate_win_probabilities_uses_the_mover_perspective():
+    moves = [
+        {"fen_before": WHITE_FEN, "analysis": _cp(0)},
+        {"fen_before": BLACK_FEN, "analysis": _cp(0)},
+        {"fen_before": WHITE_FEN, "analysis": _cp(0), "book": True}
+    ]
+
+    annotate_win_probabilities(moves, _cp(300))
+
+    assert moves[0]["accuracy"] == 100.0
+    assert moves[1]["accuracy"] == 100.0
+    assert moves[2]["accuracy"] is None
+    assert moves[0]["analysis"]["win_probability"] == pytest.approx(50.0)
+
+
+def test_annotate_win_probabilities_prefers_the_played_move_score():
+    moves = [
+        {"fen_before": WHITE_FEN, "analysis": _cp(0), "played_move_analysis": _cp(-300)},
+        {"fen_before": BLACK_FEN, "analysis": _cp(0)}
+    ]
+
+    annotate_win_probabilities(moves, _cp(0))
+
+    assert moves[0]["accuracy"] < 50
+
+
+def test_compute_player_stats():
+    moves = [
+        {"fen_before": WHITE_FEN, "analysis": {"win_probability": 50.0}, "accuracy": 100.0, "cp_loss": 0},
+        {"fen_before": BLACK_FEN, "analysis": {"win_probability": 50.0}, "accuracy": 80.0, "cp_loss": 40},
+        {"fen_before": WHITE_FEN, "analysis": {"win_probability": 55.0}, "accuracy": 90.0, "cp_loss": 20},
+        {"fen_before": BLACK_FEN, "analysis": {"win_probability": 52.0}, "accuracy": None, "cp_loss": None}
+    ]
+
+    stats = compute_player_stats(moves)
+
+    assert stats["white"]["analysed_moves"] == 2
+    assert stats["white"]["acpl"] == 10
+    assert 90 <= stats["white"]["accuracy"] <= 100
+    assert stats["black"] == {"accuracy": 80.0, "acpl": 40, "analysed_moves": 1}
+
+
+def test_compute_player_stats_handles_plies_without_scores():
+    moves = [
+        {"fen_before": WHITE_FEN, "analysis": {"score_type": "skipped"}, "accuracy": None},
+        {"fen_before": BLACK_FEN, "analysis": {"win_probability": 40.0}, "accuracy": 70.0, "cp_loss": 60},
+        {"fen_before": WHITE_FEN, "analysis": {"score_type": "skipped"}, "accuracy": None},
+        {"fen_before": BLACK_FEN, "analysis": {"win_probability": 45.0}, "accuracy": 90.0, "cp_loss": 10}
+    ]
+
+    stats = compute_player_stats(moves)
+
+    assert stats["white"] == {"accuracy": None, "acpl": None, "analysed_moves": 0}
+    assert stats["black"]["analysed_moves"] == 2
+    assert 70 <= stats["black"]["accuracy"] <= 90
+
+
+def test_compute_player_stats_without_any_scores():
+    moves = [{"fen_before": WHITE_FEN, "analysis": None, "accuracy": None}]
+
+    assert compute_player_stats(moves)["white"]["accuracy"] is None
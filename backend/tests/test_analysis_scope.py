@@ -0,0 +1,136 @@
+import pytest
+
+from app.services.move_classification import DEFAULT_THRESHOLDS
+from app.services.analysis_scope import (
+    ADAPTIVE_MIN_SWEEP_MS,
+    AnalysisScope,
+    validate_scope_options,
+    resolve_scope,
+    find_critical_plies,
+    find_adaptive_plies
+)
+
+
+SETTINGS = {
+    "adaptive_analysis_enabled": False,
+    "critical_pass_depth": 10,
+    "critical_swing_cp": 100,
+    "adaptive_time_budget_s": 120,
+    "chess_com_username": "someone"
+}
+
+
+def _ply(index, cp, **extra):
+    side = "w" if index % 2 == 0 else "b"
+    return {
+        "fen_before": f"8/8/8/8/8/8/8/8 {side} - - 0 1",
+        "analysis": {"score_type": "cp", "score_value": cp},
+        **extra
+    }
+
+
+def _game(*evaluations):
+    """Plies with the given evaluations before each move; the last value is the final position."""
+    moves = [_ply(index, cp) for index, cp in enumerate(evaluations[:-1])]
+    return moves, {"score_type": "cp", "score_value": evaluations[-1]}
+
+
+def test_scope_includes():
+    assert AnalysisScope().includes(5, white_to_move=False)
+    assert AnalysisScope(mode="mine", color="white").includes(0, white_to_move=True)
+    assert not AnalysisScope(mode="mine", color="white").includes(1, white_to_move=False)
+
+    scope = AnalysisScope(mode="range", from_ply=2, to_ply=4)
+    assert [ply for ply in range(6) if scope.includes(ply, ply % 2 == 0)] == [2, 3, 4]
+    assert AnalysisScope(mode="range", to_ply=1).includes(0, True)
+
+
+def test_scope_two_pass_and_metadata():
+    assert AnalysisScope(mode="critical").two_pass
+    assert AnalysisScope(mode="adaptive").two_pass
+    assert not AnalysisScope(mode="mine").two_pass
+    assert AnalysisScope(mode="critical", quick_depth=10, swing_cp=100).to_dict() == {
+        "mode": "critical", "quick_depth": 10, "swing_cp": 100
+    }
+
+
+def test_sweep_time_ms_spreads_the_budget_share():
+    scope = AnalysisScope(mode="adaptive", time_budget_ms=100000)
+
+    assert scope.sweep_time_ms(80, 1000) == 500
+    assert scope.sweep_time_ms(10, 1000) == 1000
+    assert scope.sweep_time_ms(100000, 1000) == ADAPTIVE_MIN_SWEEP_MS
+    assert AnalysisScope(mode="critical").sweep_time_ms(80, 1000) == 1000
+
+
+def test_full_search_budget_ms():
+    scope = AnalysisScope(mode="adaptive", time_budget_ms=100000)
+
+    assert scope.full_search_budget_ms(30000) == 70000
+    assert scope.full_search_budget_ms(150000) == 0
+    assert AnalysisScope(mode="critical").full_search_budget_ms(0) is None
+
+
+def test_validate_scope_options():
+    assert validate_scope_options(None, None, None, None) == []
+    assert validate_scope_options("range", 2, 8, None) == []
+    assert validate_scope_options("sideways", None, None, None)
+    assert validate_scope_options("mine", None, None, None)
+    assert validate_scope_options("mine", None, None, "someone") == []
+    assert validate_scope_options("range", None, None, None)
+    assert validate_scope_options("range", 8, 2, None)
+    assert validate_scope_options("range", -1, 2, None)
+    assert validate_scope_options("all", 0, 2, None)
+
+
+def test_resolve_scope_defaults_and_modes():
+    assert resolve_scope(None, "", SETTINGS).mode == "all"
+    assert resolve_scope(None, "", {**SETTINGS, "adaptive_analysis_enabled": True}).mode == "adaptive"
+    assert resolve_scope({"scope": "all"}, "", {**SETTINGS, "adaptive_analysis_enabled": True}).mode == "all"
+
+    critical = resolve_scope({"scope": "critical"}, "", SETTINGS)
+    assert (critical.quick_depth, critical.swing_cp) == (10, 100)
+
+    adaptive = resolve_scope({"scope": "adaptive"}, "", SETTINGS)
+    assert adaptive.time_budget_ms == 120000
+
+    ranged = resolve_scope({"scope": "range", "from_ply": 3}, "", SETTINGS)
+    assert (ranged.from_ply, ranged.to_ply) == (3, None)
+
+    with pytest.raises(ValueError):
+        resolve_scope({"scope": "sideways"}, "", SETTINGS)
+
+
+def test_find_critical_plies():
+    moves, final = _game(0, 0, 150, 105, 60, -240)
+
+    assert find_critical_plies(moves, final, 100) == [1, 4]
+    assert find_critical_plies(moves, final, 400) == []
+
+
+def test_find_critical_plies_leaves_out_book_and_clamps_decided_positions():
+    moves, final = _game(0, 300, 2000, 1200)
+    moves[0]["book"] = True
+
+    assert find_critical_plies(moves, final, 100) == [1]
+
+
+def test_find_critical_plies_prefers_the_played_move_score():
+    moves, final = _game(0, 0, 0)
+    moves[0]["played_move_analysis"] = {"score_type": "cp", "score_value": -200}
+
+    assert find_critical_plies(moves, final, 100) == [0]
+
+
+def test_find_adaptive_plies_orders_swings_then_unclear_moves():
+    moves, final = _game(0, 0, 150, 105, 60, -240)
+
+    # Swings largest first, then ply 2 whose 45 cp loss is close to the inaccuracy threshold
+    assert find_adaptive_plies(moves, final, 100, DEFAULT_THRESHOLDS) == [4, 1, 2]
+
+
+def test_find_adaptive_plies_sorts_unclear_moves_by_distance():
+    moves, final = _game(0, 0, 90, 40, 40, 40)
+
+    # Ply 1: 90 cp loss for Black (0.1 from "mistake"); ply 2: 50 cp for White (on "inaccuracy")
+    assert find_adaptive_plies(moves, final, 200, DEFAULT_THRESHOLDS) == [2, 1]
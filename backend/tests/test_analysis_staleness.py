@@ -0,0 +1,122 @@
+import json
+from types import SimpleNamespace
+
+from app.services.analysis_staleness import (
+    current_search_limit,
+    current_quick_depth,
+    settings_fingerprint,
+    is_analysis_weaker
+)
+
+
+SETTINGS = {
+    "stockfish_path": "/engines/stockfish",
+    "stockfish_threads": 4,
+    "analysis_depth": 20,
+    "analysis_time_ms": 1000,
+    "analysis_multipv": 3,
+    "analysis_limit_mode": "depth_time",
+    "analysis_nodes": 1000000,
+    "analysis_mate_moves": 5,
+    "deterministic_mode": False,
+    "adaptive_analysis_enabled": False,
+    "critical_pass_depth": 10,
+    "engine_options": {}
+}
+
+
+def _settings(**changes):
+    return {**SETTINGS, **changes}
+
+
+def _analysis(made_with=SETTINGS, fingerprint=None, stored=..., **columns):
+    """Stored analysis made with the given settings; keyword arguments replace its columns."""
+    limit = current_search_limit(made_with)
+    if stored is ...:
+        stored = {"limit": limit, "deterministic": made_with["deterministic_mode"]}
+    values = {
+        "engine_path": made_with["stockfish_path"],
+        "depth": limit.get("depth"),
+        "time_ms": limit.get("time_ms"),
+        "multipv": made_with["analysis_multipv"],
+        **columns
+    }
+    return SimpleNamespace(
+        settings_fingerprint=fingerprint,
+        settings=json.dumps(stored) if stored is not None else None,
+        **values
+    )
+
+
+def test_current_search_limit():
+    assert current_search_limit(SETTINGS) == {"mode": "depth_time", "depth": 20, "time_ms": 1000}
+    assert current_search_limit(_settings(analysis_limit_mode="mate")) == {"mode": "mate", "mate_moves": 5, "time_ms": 1000}
+    assert current_search_limit(_settings(deterministic_mode=True)) == {"mode": "nodes", "nodes": 1000000}
+
+
+def test_current_quick_depth():
+    assert current_quick_depth(SETTINGS) is None
+    assert current_quick_depth(_settings(adaptive_analysis_enabled=True)) == 10
+    assert current_quick_depth(_settings(analysis_scope={"mode": "critical", "quick_depth": 8})) == 8
+    assert current_quick_depth(_settings(adaptive_analysis_enabled=True, analysis_scope={"mode": "all"})) is None
+
+
+def test_settings_fingerprint():
+    assert settings_fingerprint(SETTINGS) == settings_fingerprint(dict(SETTINGS))
+    assert settings_fingerprint(SETTINGS) != settings_fingerprint(_settings(analysis_depth=22))
+    assert settings_fingerprint(SETTINGS) != settings_fingerprint(_settings(engine_options={"Contempt": 10}))
+    assert settings_fingerprint(SETTINGS) != settings_fingerprint(_settings(adaptive_analysis_enabled=True))
+    # Settings the limit mode doesn't use don't change the fingerprint
+    nodes = _settings(analysis_limit_mode="nodes")
+    assert settings_fingerprint(nodes) == settings_fingerprint({**nodes, "analysis_depth": 30})
+
+
+def test_matching_analysis_is_not_weaker():
+    assert not is_analysis_weaker(_analysis(fingerprint=settings_fingerprint(SETTINGS)), SETTINGS)
+    assert not is_analysis_weaker(_analysis(), SETTINGS)
+
+
+def test_stronger_analysis_is_not_weaker():
+    assert not is_analysis_weaker(_analysis(_settings(analysis_depth=25, analysis_time_ms=2000, analysis_multipv=5)), SETTINGS)
+
+
+def test_lower_limits_are_weaker():
+    assert is_analysis_weaker(_analysis(_settings(analysis_depth=18)), SETTINGS)
+    assert is_analysis_weaker(_analysis(_settings(analysis_time_ms=500)), SETTINGS)
+    assert is_analysis_weaker(_analysis(multipv=1), SETTINGS)
+
+    nodes = _settings(analysis_limit_mode="nodes")
+    assert is_analysis_weaker(_analysis(_settings(analysis_limit_mode="nodes", analysis_nodes=500000)), nodes)
+    assert not is_analysis_weaker(_analysis(nodes), nodes)
+
+
+def test_other_engine_limit_mode_or_determinism_is_weaker():
+    assert is_analysis_weaker(_analysis(engine_path="/engines/other"), SETTINGS)
+    assert is_analysis_weaker(_analysis(_settings(analysis_limit_mode="nodes")), SETTINGS)
+    assert is_analysis_weaker(_analysis(), _settings(deterministic_mode=True))
+
+
+def test_analysis_without_recorded_limit_counts_as_depth_and_time():
+    assert not is_analysis_weaker(_analysis(stored=None), SETTINGS)
+    assert is_analysis_weaker(_analysis(stored=None, depth=15), SETTINGS)
+    assert is_analysis_weaker(_analysis(stored=None), _settings(analysis_limit_mode="nodes"))
+
+
+def test_two_pass_analyses():
+    limit = current_search_limit(SETTINGS)
+    two_pass = {"limit": limit, "deterministic": False, "two_pass": True, "quick_depth": 10}
+    adaptive = _settings(adaptive_analysis_enabled=True)
+
+    assert is_analysis_weaker(_analysis(stored=two_pass), SETTINGS)
+    assert not is_analysis_weaker(_analysis(stored=two_pass), adaptive)
+    assert is_analysis_weaker(_analysis(stored=two_pass), _settings(adaptive_analysis_enabled=True, critical_pass_depth=12))
+    # A full analysis is never weaker than a two-pass one
+    assert not is_analysis_weaker(_analysis(), adaptive)
+
+
+def test_two_pass_analyses_from_before_two_pass_was_recorded():
+    limit = current_search_limit(SETTINGS)
+    legacy = {"limit": limit, "deterministic": False, "scope": {"mode": "critical", "quick_depth": 10, "swing_cp": 100}}
+
+    assert is_analysis_weaker(_analysis(stored=legacy), SETTINGS)
+    assert not is_analysis_weaker(_analysis(stored=legacy), _settings(adaptive_analysis_enabled=True))
@@ -0,0 +1,143 @@
+from app.services.move_classification import (
+    DEFAULT_THRESHOLDS,
+    MATE_SCORE_CP,
+    CP_LOSS_EVAL_CAP,
+    score_to_cp,
+    wdl_expected_score,
+    compute_cp_loss,
+    is_sacrifice,
+    classify_move,
+    classify_tablebase_move,
+    classify_moves
+)
+
+
+START_FEN = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+AFTER_E4_FEN = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1"
+
+
+def _cp(value, **extra):
+    return {"score_type": "cp", "score_value": value, **extra}
+
+
+def _mate(value, fen=START_FEN):
+    return {"score_type": "mate", "score_value": value, "fen": fen}
+
+
+def test_score_to_cp_maps_mates_so_faster_mates_are_worth_more():
+    assert score_to_cp(_cp(-45)) == -45
+    assert score_to_cp(_mate(1)) == MATE_SCORE_CP - 100
+    assert score_to_cp(_mate(3)) < score_to_cp(_mate(1))
+    assert score_to_cp(_mate(-2)) == -(MATE_SCORE_CP - 200)
+
+
+def test_score_to_cp_mate_zero_loses_for_the_side_to_move():
+    assert score_to_cp(_mate(0, START_FEN)) == -MATE_SCORE_CP
+    assert score_to_cp(_mate(0, AFTER_E4_FEN)) == MATE_SCORE_CP
+
+
+def test_score_to_cp_without_usable_score():
+    assert score_to_cp(None) is None
+    assert score_to_cp({"score_type": "error", "score_value": 0}) is None
+    assert score_to_cp({"score_type": "skipped", "score_value": None}) is None
+
+
+def test_wdl_expected_score_counts_draws_half():
+    assert wdl_expected_score({"win": 500, "draw": 500, "loss": 0}) == 75.0
+    assert wdl_expected_score({"win": 0, "draw": 0, "loss": 0}) == 50.0
+
+
+def test_compute_cp_loss_per_side_and_never_negative():
+    assert compute_cp_loss(50, -30, white_moved=True) == 80
+    assert compute_cp_loss(50, -30, white_moved=False) == 0
+    assert compute_cp_loss(-20, 60, white_moved=False) == 80
+
+
+def test_compute_cp_loss_clamps_decided_positions():
+    assert compute_cp_loss(1500, 900, white_moved=True) == CP_LOSS_EVAL_CAP - 900
+    assert compute_cp_loss(MATE_SCORE_CP - 300, 1500, white_moved=True) == 0
+
+
+def test_classify_move_thresholds():
+    thresholds = DEFAULT_THRESHOLDS
+
+    assert classify_move(0, False, thresholds) == "best"
+    assert classify_move(thresholds["best"], False, thresholds) == "best"
+    assert classify_move(thresholds["best"] + 1, False, thresholds) == "good"
+    assert classify_move(thresholds["inaccuracy"], False, thresholds) == "inaccuracy"
+    assert classify_move(thresholds["mistake"], False, thresholds) == "mistake"
+    assert classify_move(thresholds["blunder"], False, thresholds) == "blunder"
+    assert classify_move(thresholds["blunder"], True, thresholds) == "best"
+
+
+def test_classify_move_brilliant_needs_a_sound_sacrifice():
+    thresholds = DEFAULT_THRESHOLDS
+
+    assert classify_move(0, True, thresholds, sacrifice=True, eval_after_for_mover=40) == "brilliant"
+    assert classify_move(0, True, thresholds, sacrifice=True, eval_after_for_mover=-40) == "best"
+    assert classify_move(80, False, thresholds, sacrifice=True, eval_after_for_mover=40) == "inaccuracy"
+
+
+def test_classify_tablebase_move():
+    assert classify_tablebase_move(1, 1, white_moved=True) == "best"
+    assert classify_tablebase_move(1, 0, white_moved=True) == "blunder"
+    assert classify_tablebase_move(0, 1, white_moved=False) == "blunder"
+    assert classify_tablebase_move(-1, 0, white_moved=False) == "blunder"
+    assert classify_tablebase_move(0, -1, white_moved=False) == "best"
+
+
+def test_is_sacrifice():
+    # Knight to a square attacked by a pawn
+    assert is_sacrifice("4k3/8/3p4/8/8/3N4/8/4K3 w - - 0 1", "d3e5")
+    # Knight to an unattacked square
+    assert not is_sacrifice("4k3/8/3p4/8/8/3N4/8/4K3 w - - 0 1", "d3f4")
+    # Trading a knight for a knight
+    assert not is_sacrifice("4k3/8/3p4/4n3/8/3N4/8/4K3 w - - 0 1", "d3e5")
+    # Pawn moves never count
+    assert not is_sacrifice("4k3/8/3p4/8/4P3/8/8/4K3 w - - 0 1", "e4e5")
+    # Rook attacked by the queen: a sacrifice only when undefended
+    assert is_sacrifice("4k3/8/8/3q4/8/8/8/R3K3 w - - 0 1", "a1a5")
+    assert not is_sacrifice("4k3/8/8/3q4/8/8/R7/R3K3 w - - 0 1", "a2a5")
+
+
+def test_classify_moves_uses_the_next_evaluation_and_counts_per_player():
+    moves = [
+        {"fen_before": START_FEN, "uci": "e2e4", "analysis": _cp(30, best_move="e2e4")},
+        {"fen_before": AFTER_E4_FEN, "uci": "f7f6", "analysis": _cp(20, best_move="e7e5")}
+    ]
+
+    summary = classify_moves(moves, _cp(150), DEFAULT_THRESHOLDS)
+
+    assert moves[0]["cp_loss"] == 10
+    assert moves[0]["classification"] == "best"
+    assert moves[1]["cp_loss"] == 130
+    assert moves[1]["classification"] == "mistake"
+    assert summary["white"]["best"] == 1
+    assert summary["black"]["mistake"] == 1
+
+
+def test_classify_moves_marks_book_moves_and_missing_scores():
+    moves = [
+        {"fen_before": START_FEN, "uci": "e2e4", "analysis": _cp(30), "book": True},
+        {"fen_before": AFTER_E4_FEN, "uci": "e7e5", "analysis": {"score_type": "error", "score_value": 0}}
+    ]
+
+    summary = classify_moves(moves, _cp(25), DEFAULT_THRESHOLDS)
+
+    assert moves[0]["classification"] == "book"
+    assert moves[0]["cp_loss"] is None
+    assert moves[1]["classification"] is None
+    assert summary["white"]["book"] == 1
+
+
+def test_classify_moves_forgives_losses_the_wdl_says_are_negligible():
+    wdl = {"win": 0, "draw": 1000, "loss": 0}
+    moves = [
+        {"fen_before": START_FEN, "uci": "e2e4", "analysis": _cp(250, best_move="d2d4", wdl=wdl)}
+    ]
+
+    classify_moves(moves, _cp(0, wdl=wdl), DEFAULT_THRESHOLDS)
+
+    assert moves[0]["cp_loss"] == 250
+    assert moves[0]["wdl_loss"] == 0.0
+    assert moves[0]["classification"] == "good"
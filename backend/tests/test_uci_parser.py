@@ -0,0 +1,136 @@
+from app.services.uci_parser import parse_info_line, parse_bestmove_line, SearchCollector
+
+
+def test_parse_info_line_reads_search_fields():
+    record = parse_info_line(
+        "info depth 20 seldepth 28 multipv 2 score cp -35 wdl 120 700 180 nodes 123456 "
+        "nps 987654 hashfull 412 tbhits 3 time 125 pv e7e5 g1f3 b8c6"
+    )
+
+    assert record.depth == 20
+    assert record.seldepth == 28
+    assert record.multipv == 2
+    assert record.score_cp == -35
+    assert record.score_mate is None
+    assert record.wdl == [120, 700, 180]
+    assert record.nodes == 123456
+    assert record.nps == 987654
+    assert record.hashfull == 412
+    assert record.tbhits == 3
+    assert record.time_ms == 125
+    assert record.pv == ["e7e5", "g1f3", "b8c6"]
+    assert record.is_exact
+
+
+def test_parse_info_line_reads_mate_scores_and_bounds():
+    mate = parse_info_line("info depth 30 score mate -3 pv e1e2")
+    lower = parse_info_line("info depth 12 score cp 80 lowerbound pv d2d4")
+    upper = parse_info_line("info depth 12 score cp 10 upperbound pv d2d4")
+
+    assert mate.score_mate == -3 and mate.score_cp is None
+    assert lower.bound == "lowerbound" and not lower.is_exact
+    assert upper.bound == "upperbound" and not upper.is_exact
+
+
+def test_parse_info_line_keeps_string_and_skips_malformed_values():
+    text = parse_info_line("info string NNUE evaluation using nn-1234.nnue")
+    malformed = parse_info_line("info depth x score cp 15 pv e2e4")
+
+    assert text.string == "NNUE evaluation using nn-1234.nnue"
+    assert not text.has_score
+    assert malformed.depth is None
+    assert malformed.score_cp == 15
+    assert malformed.pv == ["e2e4"]
+
+
+def test_parse_info_line_ignores_other_lines():
+    assert parse_info_line("bestmove e2e4") is None
+    assert parse_info_line("") is None
+
+
+def test_parse_bestmove_line():
+    assert parse_bestmove_line("bestmove e2e4 ponder e7e5") == ("e2e4", "e7e5")
+    assert parse_bestmove_line("bestmove g1f3") == ("g1f3", None)
+    assert parse_bestmove_line("bestmove (none)") == (None, None)
+    assert parse_bestmove_line("info depth 1") is None
+
+
+def _collect(*lines):
+    collector = SearchCollector()
+    for line in lines:
+        collector.add(parse_info_line(line))
+    return collector
+
+
+def test_collector_keeps_deepest_exact_record_per_line():
+    collector = _collect(
+        "info depth 10 multipv 1 score cp 20 pv e2e4",
+        "info depth 10 multipv 2 score cp 10 pv d2d4",
+        "info depth 11 multipv 1 score cp 25 pv e2e4 e7e5",
+        "info depth 11 multipv 2 score cp 5 pv c2c4"
+    )
+
+    lines = collector.lines()
+    assert [record.multipv for record in lines] == [1, 2]
+    assert lines[0].depth == 11 and lines[0].pv == ["e2e4", "e7e5"]
+    assert lines[1].pv == ["c2c4"]
+
+
+def test_collector_prefers_exact_over_deeper_bound():
+    collector = _collect(
+        "info depth 14 score cp 30 pv e2e4",
+        "info depth 15 score cp 60 lowerbound pv e2e4"
+    )
+
+    [record] = collector.lines()
+    assert record.depth == 14
+    assert record.is_exact
+
+
+def test_collector_falls_back_to_bound_without_exact_record():
+    collector = _collect("info depth 15 score cp 60 lowerbound pv e2e4")
+
+    [record] = collector.lines()
+    assert record.bound == "lowerbound"
+
+
+def test_collector_keeps_scored_records_without_pv():
+    collector = _collect("info depth 0 score mate 0")
+
+    [record] = collector.lines()
+    assert record.score_mate == 0
+    assert record.pv == []
+
+
+def test_collector_prefers_records_with_pv():
+    collector = _collect(
+        "info depth 5 score cp 3 pv e2e4",
+        "info depth 6 score cp 4"
+    )
+
+    [record] = collector.lines()
+    assert record.depth == 5
+    assert record.pv == ["e2e4"]
+
+
+def test_collector_ignores_unscored_records_and_tracks_stats():
+    collector = _collect(
+        "info depth 8 currmove e2e4 currmovenumber 1",
+        "info depth 8 seldepth 12 score cp 12 nodes 5000 nps 100000 time 50 pv e2e4",
+        "info nodes 6000 nps 120000 time 55 hashfull 3"
+    )
+
+    assert len(collector.lines()) == 1
+    assert collector.search_stats() == {
+        "depth": None,
+        "seldepth": None,
+        "nodes": 6000,
+        "nps": 120000,
+        "hashfull": 3,
+        "tbhits": None,
+        "time_ms": 55
+    }
+
+
+def test_collector_stats_are_empty_without_records():
+    assert SearchCollector().search_stats()["nodes"] is None
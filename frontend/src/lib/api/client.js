@@ -81,6 +81,7 @@ export async function getGames(skip = 0, limit = 100, filters = {}, sort = {}) {
   if (filters.date_from) params.append('date_from', filters.date_from);
   if (filters.date_to) params.append('date_to', filters.date_to);
   if (filters.status) params.append('status', filters.status);
+  if (filters.opening) params.append('opening', filters.opening);
 
   // Add sorting
   if (sort.sort_by) params.append('sort_by', sort.sort_by);
@@ -97,6 +98,10 @@ export async function getGamesStats() {
   return apiFetch('/api/games/stats');
 }
 
+export async function getOpenings() {
+  return apiFetch('/api/games/openings');
+}
+
 // Database Management API
 export async function clearDatabase() {
   return apiFetch('/api/database/clear', {
@@ -153,12 +158,142 @@ export async function uploadDatabase(file) {
 }
 
 // Game Analysis API
-export async function analyzeGame(gameId) {
+// Adds the game to the background analysis queue.
+// Pass options ({ reanalyze, depth, time_ms, ... }) to analyse again with other settings.
+export async function analyzeGame(gameId, options = null) {
   return apiFetch(`/api/games/${gameId}/analyze`, {
     method: 'POST',
+    ...(options ? { body: JSON.stringify(options) } : {}),
+  });
+}
+
+// Removes the game from the queue or stops its running analysis
+export async function cancelGameAnalysis(gameId) {
+  return apiFetch(`/api/games/${gameId}/analyze/cancel`, {
+    method: 'POST',
+  });
+}
+
+export async function getGameAnalysis(gameId, version = null) {
+  const params = version !== null ? `?version=${version}` : '';
+  return apiFetch(`/api/games/${gameId}/analysis${params}`);
+}
+
+export async function getAnalysisVersions(gameId) {
+  return apiFetch(`/api/games/${gameId}/analyses`);
+}
+
+export async function diffAnalyses(gameId, base, other) {
+  return apiFetch(`/api/games/${gameId}/analyses/diff?base=${base}&other=${other}`);
+}
+
+// Analysis Queue API
+export async function getAnalysisQueue() {
+  return apiFetch('/api/analysis/queue');
+}
+
+export async function enqueueGames(filters = {}) {
+  return apiFetch('/api/analysis/queue', {
+    method: 'POST',
+    body: JSON.stringify(filters),
+  });
+}
+
+export async function enqueueAllQueuedGames() {
+  return apiFetch('/api/analysis/queue/all', {
+    method: 'POST',
+  });
+}
+
+// Re-analyses every game whose analysis is weaker than the current settings
+export async function enqueueStaleGames() {
+  return apiFetch('/api/analysis/queue/stale', {
+    method: 'POST',
+  });
+}
+
+export async function cancelAnalysisQueue() {
+  return apiFetch('/api/analysis/queue/cancel', {
+    method: 'POST',
+  });
+}
+
+// Analysis Presets API
+export async function getAnalysisPresets() {
+  return apiFetch('/api/analysis/presets');
+}
+
+export async function createAnalysisPreset(preset) {
+  return apiFetch('/api/analysis/presets', {
+    method: 'POST',
+    body: JSON.stringify(preset),
+  });
+}
+
+export async function updateAnalysisPreset(name, preset) {
+  return apiFetch(`/api/analysis/presets/${encodeURIComponent(name)}`, {
+    method: 'PUT',
+    body: JSON.stringify(preset),
+  });
+}
+
+export async function deleteAnalysisPreset(name) {
+  return apiFetch(`/api/analysis/presets/${encodeURIComponent(name)}`, {
+    method: 'DELETE',
+  });
+}
+
+// Engine Profiles API
+export async function getEngines() {
+  return apiFetch('/api/engines');
+}
+
+export async function registerEngine(engine) {
+  return apiFetch('/api/engines', {
+    method: 'POST',
+    body: JSON.stringify(engine),
+  });
+}
+
+export async function updateEngine(name, engine) {
+  return apiFetch(`/api/engines/${encodeURIComponent(name)}`, {
+    method: 'PUT',
+    body: JSON.stringify(engine),
   });
 }
 
-export async function getGameAnalysis(gameId) {
-  return apiFetch(`/api/games/${gameId}/analysis`);
+export async function deleteEngine(name) {
+  return apiFetch(`/api/engines/${encodeURIComponent(name)}`, {
+    method: 'DELETE',
+  });
+}
+
+export async function purgePositionCache() {
+  return apiFetch('/api/analysis/position-cache', {
+    method: 'DELETE',
+  });
+}
+
+/**
+ * Subscribe to live analysis progress (Server-Sent Events).
+ * Returns a function that closes the stream.
+ */
+export function subscribeAnalysisEvents(onEvent, gameId = null) {
+  const params = gameId !== null ? `?game_id=${gameId}` : '';
+  const source = new EventSource(`${API_BASE_URL}/api/analysis/events${params}`);
+
+  source.onmessage = (message) => {
+    try {
+      onEvent(JSON.parse(message.data));
+    } catch (error) {
+      console.error('[API Client] Invalid analysis event:', error);
+    }
+  };
+
+  source.onerror = (error) => {
+    // EventSource reconnects automatically
+    console.warn('[API Client] Analysis event stream error:', error);
+  };
+
+  return () => source.close();
 }